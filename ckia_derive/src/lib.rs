@@ -0,0 +1,75 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr};
+
+/// Implements `ckia::runtime_effect::SkslUniforms` for a struct with named fields.
+///
+/// Each field maps to the uniform with the same name. Use `#[sksl(name = "...")]` to map a field to a
+/// differently named uniform. Field types must implement `ckia::runtime_effect::SkslUniformValue`
+/// (`f32`, `[f32; 2..=4]`, `i32`, `[i32; 2..=4]`, `Color4f`, `Matrix`).
+#[proc_macro_derive(SkslUniforms, attributes(sksl))]
+pub fn derive_sksl_uniforms(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "SkslUniforms can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "SkslUniforms requires a struct with named fields",
+        ));
+    };
+    let mut names = vec![];
+    let mut idents = vec![];
+    let mut types = vec![];
+    for field in &fields.named {
+        let ident = field.ident.clone().expect("named field without ident");
+        let mut name = ident.to_string();
+        for attr in &field.attrs {
+            if !attr.path().is_ident("sksl") {
+                continue;
+            }
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    name = meta.value()?.parse::<LitStr>()?.value();
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown sksl attribute. expected `name = \"...\"`"))
+                }
+            })?;
+        }
+        names.push(name);
+        idents.push(ident);
+        types.push(field.ty.clone());
+    }
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::ckia::runtime_effect::SkslUniforms for #ident #ty_generics #where_clause {
+            fn uniform_fields() -> ::std::vec::Vec<(&'static str, ::ckia::RuntimeEffectUniformType)> {
+                ::std::vec![
+                    #( (#names, <#types as ::ckia::runtime_effect::SkslUniformValue>::TYPE), )*
+                ]
+            }
+            fn for_each_uniform(&self, f: &mut dyn FnMut(&'static str, &[u8])) {
+                #(
+                    {
+                        let mut bytes = ::std::vec::Vec::new();
+                        ::ckia::runtime_effect::SkslUniformValue::append_bytes(&self.#idents, &mut bytes);
+                        f(#names, &bytes);
+                    }
+                )*
+            }
+        }
+    })
+}
@@ -0,0 +1,248 @@
+use std::fmt::Display;
+
+use crate::bindings::*;
+use crate::{
+    color::{Color, ColorSpace},
+    image::Image,
+    paint::Paint,
+    surface::{Surface, SurfaceProps},
+    ImageInfo, PixelGeometry, Rect, Rsxform, SamplingOptions,
+};
+
+impl Rsxform {
+    /// `scos` and `ssin` are the cosine and sine of the rotation, multiplied by the scale.
+    pub const fn new(scos: f32, ssin: f32, tx: f32, ty: f32) -> Self {
+        Self {
+            fSCos: scos,
+            fSSin: ssin,
+            fTX: tx,
+            fTY: ty,
+        }
+    }
+    /// scales and rotates (clockwise) a sprite around its `anchor_x`, `anchor_y` (in sprite coordinates)
+    /// and moves the anchor to `x`, `y`.
+    pub fn from_radians(
+        scale: f32,
+        radians: f32,
+        x: f32,
+        y: f32,
+        anchor_x: f32,
+        anchor_y: f32,
+    ) -> Self {
+        let (sin, cos) = radians.sin_cos();
+        let (scos, ssin) = (scale * cos, scale * sin);
+        Self::new(
+            scos,
+            ssin,
+            x - scos * anchor_x + ssin * anchor_y,
+            y - ssin * anchor_x - scos * anchor_y,
+        )
+    }
+    /// only moves the sprite, so that its top left is at `x`, `y`
+    pub const fn from_translation(x: f32, y: f32) -> Self {
+        Self::new(1.0, 0.0, x, y)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AtlasError {
+    /// tex rects or colors don't have one entry per xform
+    LengthMismatch { sprites: usize, actual: usize },
+}
+impl Display for AtlasError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AtlasError::LengthMismatch { sprites, actual } => {
+                write!(
+                    f,
+                    "expected {sprites} entries (one per xform), got {actual}"
+                )
+            }
+        }
+    }
+}
+impl std::error::Error for AtlasError {}
+
+/// Images packed into a single image, for [crate::canvas::Canvas::draw_atlas].
+pub struct Atlas {
+    pub image: Image,
+    /// source rect of each sprite in [Self::image], in the order they were added to the [AtlasBuilder]
+    pub rects: Vec<Rect>,
+}
+
+/// Packs images into rows (shelves) of an atlas image that is at most `max_width` pixels wide.
+///
+/// ```rust,ignore
+/// let mut builder = AtlasBuilder::new(1024);
+/// let player = builder.add(&player_image);
+/// let atlas = builder.build().unwrap();
+/// canvas.draw_atlas(&atlas.image, &xforms, &vec![atlas.rects[player]; xforms.len()], None,
+///     BlendMode::MODULATE_SK_BLENDMODE, &SamplingOptions::LINEAR, None, &paint)?;
+/// ```
+pub struct AtlasBuilder<'a> {
+    images: Vec<&'a Image>,
+    max_width: i32,
+    padding: i32,
+}
+impl<'a> AtlasBuilder<'a> {
+    pub fn new(max_width: i32) -> Self {
+        Self {
+            images: vec![],
+            max_width,
+            padding: 1,
+        }
+    }
+    /// transparent pixels between sprites, so that filtering doesn't bleed neighbours into each other. 1 by default.
+    pub fn with_padding(mut self, padding: i32) -> Self {
+        self.padding = padding.max(0);
+        self
+    }
+    /// returns the index of the image's rect in [Atlas::rects]
+    pub fn add(&mut self, image: &'a Image) -> usize {
+        self.images.push(image);
+        self.images.len() - 1
+    }
+    /// None if there are no images, an image is wider than the max width or the surface could not be created.
+    pub fn build(&self) -> Option<Atlas> {
+        let sizes: Vec<_> = self
+            .images
+            .iter()
+            .map(|i| (i.get_width(), i.get_height()))
+            .collect();
+        let Packing {
+            positions,
+            width,
+            height,
+        } = pack(&sizes, self.max_width, self.padding)?;
+
+        let mut info = ImageInfo::default();
+        info.set_width(width);
+        info.set_height(height);
+        info.set_colorspace(Some(ColorSpace::new_srgb()));
+        let props = SurfaceProps::new(0, PixelGeometry::UNKNOWN_SK_PIXELGEOMETRY);
+        let mut surface = unsafe {
+            Surface::try_from_owned_ptr(sk_surface_new_raster(info.as_ptr(), 0, props.as_ptr()))?
+        };
+        {
+            let mut canvas = surface.get_canvas();
+            let canvas = canvas.as_mut();
+            canvas.clear(Color::TRANSPARENT);
+            let paint = Paint::default();
+            for (image, &(x, y)) in self.images.iter().zip(&positions) {
+                canvas.draw_image(image, x as f32, y as f32, &SamplingOptions::NEAREST, &paint);
+            }
+        }
+        let rects = self
+            .images
+            .iter()
+            .zip(&positions)
+            .map(|(image, &(x, y))| {
+                Rect::new(
+                    x as f32,
+                    y as f32,
+                    (x + image.get_width()) as f32,
+                    (y + image.get_height()) as f32,
+                )
+            })
+            .collect();
+        Some(Atlas {
+            image: surface.new_image_snapshot(),
+            rects,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Packing {
+    /// top left of each size, in the order they were given
+    positions: Vec<(i32, i32)>,
+    width: i32,
+    height: i32,
+}
+
+/// shelf packing of `(width, height)` sizes.
+/// None if there are no sizes or one doesn't fit in `max_width`.
+fn pack(sizes: &[(i32, i32)], max_width: i32, padding: i32) -> Option<Packing> {
+    if sizes.is_empty() || sizes.iter().any(|&(w, _)| w + 2 * padding > max_width) {
+        return None;
+    }
+    // tallest first, so that each shelf wastes less space
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(sizes[i].1));
+    let mut positions = vec![(0, 0); sizes.len()];
+    let (mut x, mut y, mut shelf_height, mut width) = (padding, padding, 0, 0);
+    for &index in &order {
+        let (w, h) = sizes[index];
+        if x + w + padding > max_width {
+            x = padding;
+            y += shelf_height + padding;
+            shelf_height = 0;
+        }
+        positions[index] = (x, y);
+        x += w + padding;
+        width = width.max(x);
+        shelf_height = shelf_height.max(h);
+    }
+    Some(Packing {
+        positions,
+        width,
+        height: y + shelf_height + padding,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{pack, Packing};
+
+    #[test]
+    pub fn test_pack_single_shelf() {
+        let Packing {
+            positions,
+            width,
+            height,
+        } = pack(&[(10, 5), (20, 8)], 100, 1).unwrap();
+        // the taller one goes first
+        assert_eq!(positions, vec![(22, 1), (1, 1)]);
+        assert_eq!((width, height), (33, 10));
+    }
+
+    #[test]
+    pub fn test_pack_overflow_starts_new_shelf() {
+        let Packing {
+            positions,
+            width,
+            height,
+        } = pack(&[(30, 10), (30, 6), (30, 4)], 64, 1).unwrap();
+        assert_eq!(positions, vec![(1, 1), (32, 1), (1, 12)]);
+        assert_eq!((width, height), (63, 17));
+        // the same sizes without padding fit on one shelf
+        let Packing {
+            positions,
+            width,
+            height,
+        } = pack(&[(30, 10), (30, 6), (30, 4)], 90, 0).unwrap();
+        assert_eq!(positions, vec![(0, 0), (30, 0), (60, 0)]);
+        assert_eq!((width, height), (90, 10));
+    }
+
+    #[test]
+    pub fn test_pack_padding() {
+        let Packing {
+            positions,
+            width,
+            height,
+        } = pack(&[(4, 4), (4, 4)], 100, 3).unwrap();
+        assert_eq!(positions, vec![(3, 3), (10, 3)]);
+        assert_eq!((width, height), (17, 10));
+        // padding counts on both sides of an image
+        assert!(pack(&[(10, 10)], 15, 3).is_none());
+        assert!(pack(&[(10, 10)], 16, 3).is_some());
+    }
+
+    #[test]
+    pub fn test_pack_rejects_too_wide_or_empty() {
+        assert!(pack(&[], 100, 1).is_none());
+        assert!(pack(&[(10, 10), (200, 10)], 100, 1).is_none());
+        assert!(pack(&[(100, 10)], 100, 0).is_some());
+    }
+}
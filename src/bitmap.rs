@@ -2,7 +2,12 @@ use std::mem::transmute;
 
 use crate::bindings::*;
 
-use crate::{color::Color, pixmap::PixMap, skia_wrapper, IRect, ImageInfo};
+use crate::{
+    color::Color,
+    pixel_view::{PixelFormat, PixelView},
+    pixmap::PixMap,
+    skia_wrapper, IRect, ImageInfo,
+};
 
 skia_wrapper!(unique, BitMap, sk_bitmap_t, sk_bitmap_destructor);
 
@@ -26,6 +31,12 @@ impl BitMap {
             std::slice::from_raw_parts_mut(ptr_to_pixels as _, length)
         }
     }
+    /// typed view over our pixels. None if the color type is not `F::COLOR_TYPE`.
+    pub fn pixel_view<F: PixelFormat>(&mut self) -> Option<PixelView<'_, F>> {
+        let info = self.get_info();
+        let row_bytes = self.get_row_bytes();
+        PixelView::new(&info, self.get_pixels(), row_bytes)
+    }
     pub fn get_row_bytes(&mut self) -> usize {
         unsafe { sk_bitmap_get_row_bytes(self.inner) }
     }
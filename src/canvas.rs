@@ -1,11 +1,20 @@
+use std::{
+    borrow::{Borrow, BorrowMut},
+    ffi::CStr,
+    fmt::Display,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+};
+
 use crate::filter::ImageFilter;
 use crate::{bindings::*, SkiaOptPtr};
 
 use crate::{
-    bitmap::BitMap, color::Color, font::Font, image::Image, paint::Paint, path::SkiaPath,
-    picture::Picture, region::Region, rrect::RRect, skia_wrapper, text_blob::TextBlob, BlendMode,
-    ClipOp, Color4f, IRect, Matrix, Matrix44, Point, PointMode, Rect, SamplingOptions,
-    TextEncoding,
+    atlas::AtlasError, bitmap::BitMap, color::Color, data::SkiaData, font::Font, image::Image,
+    paint::Paint, patch::CoonsPatch, path::SkiaPath, picture::Picture, region::Region,
+    rrect::RRect, skia_wrapper, surface::SurfaceProps, text_blob::TextBlob, vertices::Vertices,
+    BlendMode, ClipOp, Color4f, FilterMode, IRect, ImageInfo, Lattice, LatticeRecttype, Matrix,
+    Matrix44, Point, PointMode, Rect, Rsxform, SamplingOptions, TextEncoding,
 };
 
 skia_wrapper!(unique, Canvas, sk_canvas_t, sk_canvas_destroy);
@@ -18,6 +27,41 @@ impl Canvas {
             }
         }
     }
+    /// draws directly into `pixels`, which is laid out as described by `info` and `row_bytes`.
+    ///
+    /// Returns None if `row_bytes` is too small for the width, if `pixels` is shorter than `height * row_bytes`
+    /// (even though the last row doesn't need padding) or if skia doesn't support rendering into `info`
+    /// (eg: unknown color type or more than 2 GiB of pixels).
+    pub fn new_raster<'a>(
+        info: &ImageInfo,
+        pixels: &'a mut [u8],
+        row_bytes: usize,
+        props: &SurfaceProps,
+    ) -> Option<BorrowedCanvas<'a>> {
+        let height = info.get_height().max(0) as usize;
+        if row_bytes < info.min_row_bytes()
+            || height
+                .checked_mul(row_bytes)
+                .map_or(true, |size| pixels.len() < size)
+        {
+            return None;
+        }
+        unsafe {
+            let canvas = sk_canvas_new_from_raster(
+                info.as_ptr(),
+                pixels.as_mut_ptr() as _,
+                row_bytes,
+                props.as_ptr(),
+            );
+            if canvas.is_null() {
+                return None;
+            }
+            Some(BorrowedCanvas {
+                canvas,
+                phantom: PhantomData,
+            })
+        }
+    }
     pub fn clear(&mut self, color: Color) {
         unsafe {
             sk_canvas_clear(self.inner, color.0);
@@ -141,9 +185,38 @@ impl Canvas {
             )
         }
     }
+    pub fn save_layer_rec(&mut self, rec: &SaveLayerRec) -> i32 {
+        unsafe {
+            sk_canvas_save_layer(
+                self.as_ptr_mut(),
+                rec.bounds.or_null(),
+                rec.paint.or_null(),
+                rec.backdrop.or_null(),
+                rec.flags,
+            )
+        }
+    }
     pub fn restore(&mut self) {
         unsafe { sk_canvas_restore(self.as_ptr_mut()) }
     }
+    /// saves the matrix and clip, which are restored when the returned guard is dropped (even on panics or early returns).
+    ///
+    /// ```rust,ignore
+    /// {
+    ///     let mut canvas = canvas.scoped();
+    ///     canvas.translate(10.0, 10.0);
+    ///     draw_widget(&mut canvas)?;
+    /// } // restored here
+    /// ```
+    pub fn scoped(&mut self) -> ScopedCanvas<'_> {
+        let restore_count = self.save();
+        ScopedCanvas::new(self, restore_count)
+    }
+    /// same as [Self::scoped], but also starts a layer which is drawn into the canvas when the guard is dropped.
+    pub fn layer(&mut self, rec: &SaveLayerRec) -> ScopedCanvas<'_> {
+        let restore_count = self.save_layer_rec(rec);
+        ScopedCanvas::new(self, restore_count)
+    }
     pub fn translate(&mut self, dx: f32, dy: f32) {
         unsafe { sk_canvas_translate(self.as_ptr_mut(), dx, dy) }
     }
@@ -247,92 +320,206 @@ impl Canvas {
     pub fn flush(&mut self) {
         unsafe { sk_canvas_flush(self.as_ptr_mut()) }
     }
+    /// Attaches arbitrary `value` to `rect` under `key`. Only some backends (eg: pdf) use annotations,
+    /// others ignore them. `rect` is transformed by the current matrix.
+    pub fn draw_annotation(&mut self, rect: &Rect, key: &CStr, value: &SkiaData) {
+        unsafe {
+            sk_canvas_draw_annotation(self.as_ptr_mut(), rect.as_ptr(), key.as_ptr(), value.inner)
+        }
+    }
+    /// Makes `rect` a clickable link to `url` in pdf documents.
+    ///
+    /// skia treats the annotations as C strings, so everything after an interior NUL byte is ignored.
+    pub fn draw_url_annotation(&mut self, rect: &Rect, url: &str) {
+        let value = annotation_cstr_data(url);
+        unsafe { sk_canvas_draw_url_annotation(self.as_ptr_mut(), rect.as_ptr(), value.inner) }
+    }
+    /// Marks `point` as a jump target called `name` in pdf documents.
+    /// Links created with [Self::draw_link_destination_annotation] using the same name will jump here.
+    pub fn draw_named_destination_annotation(&mut self, point: &Point, name: &str) {
+        let value = annotation_cstr_data(name);
+        unsafe {
+            sk_canvas_draw_named_destination_annotation(
+                self.as_ptr_mut(),
+                point.as_ptr(),
+                value.inner,
+            )
+        }
+    }
+    /// Makes `rect` a clickable link to the named destination `name` (can be on any page of the document).
+    pub fn draw_link_destination_annotation(&mut self, rect: &Rect, name: &str) {
+        let value = annotation_cstr_data(name);
+        unsafe {
+            sk_canvas_draw_link_destination_annotation(
+                self.as_ptr_mut(),
+                rect.as_ptr(),
+                value.inner,
+            )
+        }
+    }
 
-    /*
-    pub fn sk_canvas_new_from_raster(
-        cinfo: *const sk_imageinfo_t,
-        pixels: *mut ::std::os::raw::c_void,
-        rowBytes: usize,
-        props: *const sk_surfaceprops_t,
-    ) -> *mut sk_canvas_t;
-    pub fn sk_canvas_draw_annotation(
-        t: *mut sk_canvas_t,
-        rect: *const sk_rect_t,
-        key: *const ::std::os::raw::c_char,
-        value: *mut sk_data_t,
-    );
-    pub fn sk_canvas_draw_url_annotation(
-        t: *mut sk_canvas_t,
-        rect: *const sk_rect_t,
-        value: *mut sk_data_t,
-    );
-    pub fn sk_canvas_draw_named_destination_annotation(
-        t: *mut sk_canvas_t,
-        point: *const sk_point_t,
-        value: *mut sk_data_t,
-    );
-    pub fn sk_canvas_draw_link_destination_annotation(
-        t: *mut sk_canvas_t,
-        rect: *const sk_rect_t,
-        value: *mut sk_data_t,
-    );
-    pub fn sk_canvas_draw_image_lattice(
-        ccanvas: *mut sk_canvas_t,
-        image: *const sk_image_t,
-        lattice: *const sk_lattice_t,
-        dst: *const sk_rect_t,
-        mode: sk_filter_mode_t,
-        paint: *const sk_paint_t,
-    );
-    pub fn sk_canvas_draw_image_nine(
-        ccanvas: *mut sk_canvas_t,
-        image: *const sk_image_t,
-        center: *const sk_irect_t,
-        dst: *const sk_rect_t,
-        mode: sk_filter_mode_t,
-        paint: *const sk_paint_t,
-    );
-    pub fn sk_canvas_draw_vertices(
-        ccanvas: *mut sk_canvas_t,
-        vertices: *const sk_vertices_t,
-        mode: sk_blendmode_t,
-        paint: *const sk_paint_t,
-    );
-    pub fn sk_canvas_draw_arc(
-        ccanvas: *mut sk_canvas_t,
-        oval: *const sk_rect_t,
-        startAngle: f32,
-        sweepAngle: f32,
-        useCenter: bool,
-        paint: *const sk_paint_t,
-    );
-    pub fn sk_canvas_draw_drrect(
-        ccanvas: *mut sk_canvas_t,
-        outer: *const sk_rrect_t,
-        inner: *const sk_rrect_t,
-        paint: *const sk_paint_t,
-    );
-    pub fn sk_canvas_draw_atlas(
-        ccanvas: *mut sk_canvas_t,
-        atlas: *const sk_image_t,
-        xform: *const sk_rsxform_t,
-        tex: *const sk_rect_t,
-        colors: *const sk_color_t,
-        count: ::std::os::raw::c_int,
-        mode: sk_blendmode_t,
-        sampling: *const sk_sampling_options_t,
-        cullRect: *const sk_rect_t,
-        paint: *const sk_paint_t,
-    );
-    pub fn sk_canvas_draw_patch(
-        ccanvas: *mut sk_canvas_t,
-        cubics: *const sk_point_t,
-        colors: *const sk_color_t,
-        texCoords: *const sk_point_t,
-        mode: sk_blendmode_t,
-        paint: *const sk_paint_t,
-    );
-     */
+    /// draws the part of the oval's outline from `start_angle` over `sweep_angle` (both in degrees, clockwise).
+    /// With `use_center`, the arc is closed through the center of the oval (a pie slice when filled).
+    pub fn draw_arc(
+        &mut self,
+        oval: &Rect,
+        start_angle: f32,
+        sweep_angle: f32,
+        use_center: bool,
+        paint: &Paint,
+    ) {
+        unsafe {
+            sk_canvas_draw_arc(
+                self.as_ptr_mut(),
+                oval.as_ptr(),
+                start_angle,
+                sweep_angle,
+                use_center,
+                paint.as_ptr(),
+            )
+        }
+    }
+    /// fills the area between `outer` and `inner`. `inner` must be contained by `outer`, otherwise nothing is drawn.
+    pub fn draw_drrect(&mut self, outer: &RRect, inner: &RRect, paint: &Paint) {
+        unsafe {
+            sk_canvas_draw_drrect(
+                self.as_ptr_mut(),
+                outer.as_ptr(),
+                inner.as_ptr(),
+                paint.as_ptr(),
+            )
+        }
+    }
+    /// draws the image as a 3x3 grid into `dst`. The corners keep their size, the edges
+    /// are stretched in one direction and `center` is stretched in both.
+    /// If `center` is not inside the image, the whole image is stretched into `dst` instead.
+    pub fn draw_image_nine(
+        &mut self,
+        image: &Image,
+        center: &IRect,
+        dst: &Rect,
+        mode: FilterMode,
+        paint: &Paint,
+    ) {
+        unsafe {
+            sk_canvas_draw_image_nine(
+                self.as_ptr_mut(),
+                image.as_ptr(),
+                center.as_ptr(),
+                dst.as_ptr(),
+                mode,
+                paint.as_ptr(),
+            )
+        }
+    }
+    /// draws the image divided by `lattice` into `dst`. See [ImageLattice].
+    pub fn draw_image_lattice(
+        &mut self,
+        image: &Image,
+        lattice: &ImageLattice,
+        dst: &Rect,
+        mode: FilterMode,
+        paint: &Paint,
+    ) -> Result<(), LatticeError> {
+        lattice.validate(image.get_width(), image.get_height())?;
+        let bounds = lattice.bounds.as_ref();
+        let native = Lattice {
+            fXDivs: lattice.x_divs.as_ptr(),
+            fYDivs: lattice.y_divs.as_ptr(),
+            fRectTypes: lattice
+                .rect_types
+                .as_ref()
+                .map_or(std::ptr::null(), |t| t.as_ptr()),
+            fXCount: lattice.x_divs.len() as _,
+            fYCount: lattice.y_divs.len() as _,
+            fBounds: bounds.or_null(),
+            fColors: lattice
+                .colors
+                .as_ref()
+                .map_or(std::ptr::null(), |c| c.as_ptr() as _),
+        };
+        unsafe {
+            sk_canvas_draw_image_lattice(
+                self.as_ptr_mut(),
+                image.as_ptr(),
+                native.as_ptr(),
+                dst.as_ptr(),
+                mode,
+                paint.as_ptr(),
+            )
+        }
+        Ok(())
+    }
+    /// draws a triangle mesh. `mode` blends the vertex colors (dst) with the paint's shader or color (src).
+    /// Without vertex colors, the mesh is drawn with the paint like any other shape.
+    pub fn draw_vertices(&mut self, vertices: &Vertices, mode: BlendMode, paint: &Paint) {
+        unsafe {
+            sk_canvas_draw_vertices(self.as_ptr_mut(), vertices.as_ptr(), mode, paint.as_ptr())
+        }
+    }
+    /// draws sprites from `atlas`. Sprite `i` is `tex[i]` of the atlas, transformed by `xform[i]`.
+    /// `colors` (one per sprite) are blended with the sprites using `mode` (sprite is src, color is dst).
+    /// `cull_rect` is an optional conservative bound of all the sprites, to skip drawing them when they are outside the clip.
+    ///
+    /// Fails if `tex` or `colors` don't have one entry per `xform`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_atlas(
+        &mut self,
+        atlas: &Image,
+        xform: &[Rsxform],
+        tex: &[Rect],
+        colors: Option<&[Color]>,
+        mode: BlendMode,
+        sampling: &SamplingOptions,
+        cull_rect: Option<&Rect>,
+        paint: &Paint,
+    ) -> Result<(), AtlasError> {
+        if let Some(actual) = [Some(tex.len()), colors.map(<[Color]>::len)]
+            .into_iter()
+            .flatten()
+            .find(|&l| l != xform.len())
+        {
+            return Err(AtlasError::LengthMismatch {
+                sprites: xform.len(),
+                actual,
+            });
+        }
+        unsafe {
+            sk_canvas_draw_atlas(
+                self.as_ptr_mut(),
+                atlas.as_ptr(),
+                xform.as_ptr(),
+                tex.as_ptr(),
+                colors.map_or(std::ptr::null(), |c| c.as_ptr() as _),
+                tex.len() as _,
+                mode,
+                sampling.as_ptr(),
+                cull_rect.or_null(),
+                paint.as_ptr(),
+            )
+        }
+        Ok(())
+    }
+    /// draws a [CoonsPatch]. `mode` blends the patch's colors (dst) with the paint's shader (src).
+    /// Without colors, the patch is filled with the paint. Texture coordinates map into the paint's shader.
+    pub fn draw_patch(&mut self, patch: &CoonsPatch, mode: BlendMode, paint: &Paint) {
+        unsafe {
+            sk_canvas_draw_patch(
+                self.as_ptr_mut(),
+                patch.cubics.as_ptr(),
+                patch
+                    .colors
+                    .as_ref()
+                    .map_or(std::ptr::null(), |c| c.as_ptr() as _),
+                patch
+                    .tex_coords
+                    .as_ref()
+                    .map_or(std::ptr::null(), |t| t.as_ptr()),
+                mode,
+                paint.as_ptr(),
+            )
+        }
+    }
     pub fn is_clip_empty(&mut self) -> bool {
         unsafe { sk_canvas_is_clip_empty(self.as_ptr_mut()) }
     }
@@ -340,3 +527,401 @@ impl Canvas {
         unsafe { sk_canvas_is_clip_rect(self.as_ptr_mut()) }
     }
 }
+/// Parameters of [Canvas::save_layer_rec] and [Canvas::layer].
+///
+/// ```rust,ignore
+/// let rec = SaveLayerRec::new().bounds(&rect).backdrop(&blur).init_with_previous(true);
+/// let mut layer = canvas.layer(&rec);
+/// ```
+#[derive(Clone, Copy, Default)]
+pub struct SaveLayerRec<'a> {
+    bounds: Option<&'a Rect>,
+    paint: Option<&'a Paint>,
+    backdrop: Option<&'a ImageFilter>,
+    flags: u32,
+}
+impl<'a> SaveLayerRec<'a> {
+    const PRESERVE_LCD_TEXT: u32 = 1 << 1;
+    const INIT_WITH_PREVIOUS: u32 = 1 << 2;
+    const F16_COLOR_TYPE: u32 = 1 << 4;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// a hint for the size of the layer. Drawing outside the bounds may be clipped.
+    pub fn bounds(mut self, bounds: &'a Rect) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+    /// alpha, blend mode, color filter and image filter of the paint are applied when the layer is drawn into the canvas.
+    pub fn paint(mut self, paint: &'a Paint) -> Self {
+        self.paint = Some(paint);
+        self
+    }
+    /// the layer starts with the previous contents of the canvas, filtered by `backdrop`. eg: blur behind a panel.
+    pub fn backdrop(mut self, backdrop: &'a ImageFilter) -> Self {
+        self.backdrop = Some(backdrop);
+        self
+    }
+    /// the layer starts with a copy of the previous contents instead of being transparent.
+    pub fn init_with_previous(self, enabled: bool) -> Self {
+        self.flag(Self::INIT_WITH_PREVIOUS, enabled)
+    }
+    /// keeps subpixel (lcd) text rendering in the layer. Only looks correct if the layer is opaque.
+    pub fn preserve_lcd_text(self, enabled: bool) -> Self {
+        self.flag(Self::PRESERVE_LCD_TEXT, enabled)
+    }
+    /// use a half float layer, to keep more precision and extended range colors.
+    pub fn f16_color_type(self, enabled: bool) -> Self {
+        self.flag(Self::F16_COLOR_TYPE, enabled)
+    }
+    fn flag(mut self, flag: u32, enabled: bool) -> Self {
+        if enabled {
+            self.flags |= flag;
+        } else {
+            self.flags &= !flag;
+        }
+        self
+    }
+}
+
+/// Restores the canvas to the state it was in before [Canvas::scoped] or [Canvas::layer] when dropped.
+///
+/// In debug builds, dropping the guard asserts that all saves made through it were restored.
+pub struct ScopedCanvas<'c> {
+    canvas: &'c mut Canvas,
+    restore_count: i32,
+}
+impl<'c> ScopedCanvas<'c> {
+    fn new(canvas: &'c mut Canvas, restore_count: i32) -> Self {
+        Self {
+            canvas,
+            restore_count,
+        }
+    }
+    /// same as dropping the guard
+    pub fn restore(self) {}
+}
+impl Drop for ScopedCanvas<'_> {
+    fn drop(&mut self) {
+        // avoid a double panic (which aborts) when unwinding through the guard
+        if !std::thread::panicking() {
+            debug_assert_eq!(
+                self.canvas.get_save_count(),
+                self.restore_count + 1,
+                "unbalanced save/restore inside a scoped canvas"
+            );
+        }
+        self.canvas.restore_to_count(self.restore_count);
+    }
+}
+impl Deref for ScopedCanvas<'_> {
+    type Target = Canvas;
+    fn deref(&self) -> &Self::Target {
+        self.canvas
+    }
+}
+impl DerefMut for ScopedCanvas<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.canvas
+    }
+}
+
+/// A canvas that draws into a pixel buffer owned by the caller. See [Canvas::new_raster].
+pub struct BorrowedCanvas<'a> {
+    // not a Canvas, so that it can't be swapped out of the borrow
+    canvas: *mut sk_canvas_t,
+    phantom: PhantomData<&'a mut [u8]>,
+}
+impl Drop for BorrowedCanvas<'_> {
+    fn drop(&mut self) {
+        unsafe { sk_canvas_destroy(self.canvas) }
+    }
+}
+impl AsMut<Canvas> for BorrowedCanvas<'_> {
+    fn as_mut(&mut self) -> &mut Canvas {
+        unsafe { std::mem::transmute::<&mut *mut sk_canvas_t, &mut Canvas>(&mut self.canvas) }
+    }
+}
+impl Borrow<Canvas> for BorrowedCanvas<'_> {
+    fn borrow(&self) -> &Canvas {
+        unsafe { std::mem::transmute::<&*mut sk_canvas_t, &Canvas>(&self.canvas) }
+    }
+}
+impl BorrowMut<Canvas> for BorrowedCanvas<'_> {
+    fn borrow_mut(&mut self) -> &mut Canvas {
+        self.as_mut()
+    }
+}
+/// annotation values are read as NUL terminated strings.
+fn annotation_cstr_data(text: &str) -> SkiaData {
+    let mut bytes = Vec::with_capacity(text.len() + 1);
+    bytes.extend_from_slice(text.as_bytes());
+    bytes.push(0);
+    SkiaData::new_with_copy(&bytes)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LatticeError {
+    /// the bounds are empty or not inside the image
+    InvalidBounds,
+    /// divisors must be inside the bounds and strictly increasing
+    InvalidDivs,
+    /// the lattice has `expected` cells, but `actual` rect types (or colors) were given
+    RectTypesLength { expected: usize, actual: usize },
+}
+impl Display for LatticeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LatticeError::InvalidBounds => {
+                write!(f, "lattice bounds are empty or outside the image")
+            }
+            LatticeError::InvalidDivs => {
+                write!(
+                    f,
+                    "lattice divisors are outside the bounds or not increasing"
+                )
+            }
+            LatticeError::RectTypesLength { expected, actual } => {
+                write!(f, "lattice has {expected} cells, but {actual} rect types")
+            }
+        }
+    }
+}
+impl std::error::Error for LatticeError {}
+
+/// Divides an image into a grid for [Canvas::draw_image_lattice].
+///
+/// The x and y divisors alternate between fixed and stretchable columns (rows) starting with a fixed one.
+/// A divisor equal to the left (top) of the bounds makes the first column (row) stretchable.
+/// Fixed cells keep their size and stretchable cells share the remaining space of the destination.
+///
+/// Each cell can be drawn from the image (default), left transparent or filled with a color.
+/// Cells are numbered row by row, there are `(x_divs.len() + 1) * (y_divs.len() + 1)` of them.
+///
+/// ```rust,ignore
+/// // a 3x3 nine patch with a transparent center
+/// let mut lattice = ImageLattice::new([8, 24], [8, 24]);
+/// lattice.set_cell(1, 1, LatticeRecttype::TRANSPARENT_SK_LATTICE_RECT_TYPE, Color::TRANSPARENT);
+/// canvas.draw_image_lattice(&image, &lattice, &dst, FilterMode::LINEAR_SK_FILTER_MODE, &paint)?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ImageLattice {
+    x_divs: Vec<i32>,
+    y_divs: Vec<i32>,
+    bounds: Option<IRect>,
+    rect_types: Option<Vec<LatticeRecttype>>,
+    colors: Option<Vec<Color>>,
+}
+impl ImageLattice {
+    pub fn new(x_divs: impl Into<Vec<i32>>, y_divs: impl Into<Vec<i32>>) -> Self {
+        Self {
+            x_divs: x_divs.into(),
+            y_divs: y_divs.into(),
+            ..Default::default()
+        }
+    }
+    /// only use this part of the image. The whole image by default.
+    pub fn with_bounds(mut self, bounds: IRect) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+    /// sets the type and color of every cell. `colors` are only used by fixed color cells.
+    pub fn with_rect_types(
+        mut self,
+        rect_types: impl Into<Vec<LatticeRecttype>>,
+        colors: impl Into<Vec<Color>>,
+    ) -> Self {
+        self.rect_types = Some(rect_types.into());
+        self.colors = Some(colors.into());
+        self
+    }
+    pub fn get_cell_count(&self) -> usize {
+        (self.x_divs.len() + 1) * (self.y_divs.len() + 1)
+    }
+    /// sets the type of a single cell. `color` is only used with [LatticeRecttype::FIXED_COLOR_SK_LATTICE_RECT_TYPE].
+    ///
+    /// panics if the cell is outside the lattice
+    pub fn set_cell(
+        &mut self,
+        column: usize,
+        row: usize,
+        rect_type: LatticeRecttype,
+        color: Color,
+    ) -> &mut Self {
+        let columns = self.x_divs.len() + 1;
+        assert!(column < columns && row < self.y_divs.len() + 1);
+        let count = self.get_cell_count();
+        let rect_types = self.rect_types.get_or_insert_with(Vec::new);
+        rect_types.resize(count, LatticeRecttype::DEFAULT_SK_LATTICE_RECT_TYPE);
+        rect_types[row * columns + column] = rect_type;
+        let colors = self.colors.get_or_insert_with(Vec::new);
+        colors.resize(count, Color::TRANSPARENT);
+        colors[row * columns + column] = color;
+        self
+    }
+    /// checks the lattice against an image of `width` x `height`
+    pub fn validate(&self, width: i32, height: i32) -> Result<(), LatticeError> {
+        let bounds = self.bounds.unwrap_or(IRect {
+            left: 0,
+            top: 0,
+            right: width,
+            bottom: height,
+        });
+        if bounds.left < 0
+            || bounds.top < 0
+            || bounds.right > width
+            || bounds.bottom > height
+            || bounds.left >= bounds.right
+            || bounds.top >= bounds.bottom
+        {
+            return Err(LatticeError::InvalidBounds);
+        }
+        let valid_divs = |divs: &[i32], start: i32, end: i32| {
+            divs.iter().all(|&d| d >= start && d < end) && divs.windows(2).all(|w| w[0] < w[1])
+        };
+        if !valid_divs(&self.x_divs, bounds.left, bounds.right)
+            || !valid_divs(&self.y_divs, bounds.top, bounds.bottom)
+        {
+            return Err(LatticeError::InvalidDivs);
+        }
+        let expected = self.get_cell_count();
+        let lengths = [
+            self.rect_types.as_ref().map(Vec::len),
+            self.colors.as_ref().map(Vec::len),
+        ];
+        if let Some(actual) = lengths.into_iter().flatten().find(|&l| l != expected) {
+            return Err(LatticeError::RectTypesLength { expected, actual });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ImageLattice, LatticeError};
+    use crate::{Color, IRect, LatticeRecttype};
+
+    #[test]
+    pub fn test_lattice_validate() {
+        assert_eq!(ImageLattice::new([8, 24], [8, 24]).validate(32, 32), Ok(()));
+        // a divisor on the left/top edge makes the first column/row stretchable
+        assert_eq!(ImageLattice::new([0, 16], [0]).validate(32, 32), Ok(()));
+        assert_eq!(ImageLattice::new([], []).validate(32, 32), Ok(()));
+    }
+
+    #[test]
+    pub fn test_lattice_divs_out_of_bounds() {
+        for (x_divs, y_divs) in [
+            (vec![8, 32], vec![8]),
+            (vec![-1, 8], vec![8]),
+            (vec![8], vec![8, 40]),
+        ] {
+            assert_eq!(
+                ImageLattice::new(x_divs, y_divs).validate(32, 32),
+                Err(LatticeError::InvalidDivs)
+            );
+        }
+        // divisors are checked against the bounds, not the image
+        let bounds = IRect {
+            left: 10,
+            top: 10,
+            right: 20,
+            bottom: 20,
+        };
+        assert_eq!(
+            ImageLattice::new([12], [12])
+                .with_bounds(bounds)
+                .validate(32, 32),
+            Ok(())
+        );
+        assert_eq!(
+            ImageLattice::new([5], [12])
+                .with_bounds(bounds)
+                .validate(32, 32),
+            Err(LatticeError::InvalidDivs)
+        );
+    }
+
+    #[test]
+    pub fn test_lattice_divs_not_increasing() {
+        assert_eq!(
+            ImageLattice::new([24, 8], [8]).validate(32, 32),
+            Err(LatticeError::InvalidDivs)
+        );
+        assert_eq!(
+            ImageLattice::new([8], [8, 8]).validate(32, 32),
+            Err(LatticeError::InvalidDivs)
+        );
+    }
+
+    #[test]
+    pub fn test_lattice_invalid_bounds() {
+        for bounds in [
+            IRect {
+                left: 0,
+                top: 0,
+                right: 40,
+                bottom: 32,
+            },
+            IRect {
+                left: 10,
+                top: 0,
+                right: 10,
+                bottom: 32,
+            },
+            IRect {
+                left: -1,
+                top: 0,
+                right: 8,
+                bottom: 8,
+            },
+        ] {
+            assert_eq!(
+                ImageLattice::new([], [])
+                    .with_bounds(bounds)
+                    .validate(32, 32),
+                Err(LatticeError::InvalidBounds)
+            );
+        }
+    }
+
+    #[test]
+    pub fn test_lattice_rect_types_length() {
+        let rect_types = [LatticeRecttype::DEFAULT_SK_LATTICE_RECT_TYPE; 4];
+        let colors = [Color::TRANSPARENT; 4];
+        assert_eq!(
+            ImageLattice::new([16], [16])
+                .with_rect_types(rect_types, colors)
+                .validate(32, 32),
+            Ok(())
+        );
+        assert_eq!(
+            ImageLattice::new([8, 24], [16])
+                .with_rect_types(rect_types, colors)
+                .validate(32, 32),
+            Err(LatticeError::RectTypesLength {
+                expected: 6,
+                actual: 4
+            })
+        );
+        assert_eq!(
+            ImageLattice::new([16], [16])
+                .with_rect_types(rect_types, [Color::TRANSPARENT; 3])
+                .validate(32, 32),
+            Err(LatticeError::RectTypesLength {
+                expected: 4,
+                actual: 3
+            })
+        );
+        // set_cell keeps the lengths in sync
+        let mut lattice = ImageLattice::new([8, 24], [8, 24]);
+        lattice.set_cell(
+            1,
+            1,
+            LatticeRecttype::FIXED_COLOR_SK_LATTICE_RECT_TYPE,
+            Color::RED,
+        );
+        assert_eq!(lattice.validate(32, 32), Ok(()));
+    }
+}
@@ -0,0 +1,966 @@
+use std::{marker::PhantomData, ops::Range, time::Duration};
+
+use crate::bindings::*;
+use crate::{
+    bitmap::BitMap,
+    data::SkiaData,
+    image::Image,
+    paint::Paint,
+    pixmap::PixMap,
+    stream::{MemoryStream, Stream},
+    surface::{Surface, SurfaceProps},
+    AlphaType, BlendMode, CodecFrameInfo, CodecOptions, CodecResult, CodecScanlineOrder,
+    CodecZeroInitialized, CodecanimationBlend, CodecanimationDisposalmethod, EncodedImageFormat,
+    EncodedOrigin, IRect, ISize, ImageInfo, Matrix, PixelGeometry, SamplingOptions,
+};
+
+impl std::fmt::Display for CodecResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use sk_codec_result_t::*;
+        f.write_str(match self {
+            SUCCESS_SK_CODEC_RESULT => "success",
+            INCOMPLETE_INPUT_SK_CODEC_RESULT => "incomplete input",
+            ERROR_IN_INPUT_SK_CODEC_RESULT => "error in input",
+            INVALID_CONVERSION_SK_CODEC_RESULT => "invalid conversion",
+            INVALID_SCALE_SK_CODEC_RESULT => "invalid scale",
+            INVALID_PARAMETERS_SK_CODEC_RESULT => "invalid parameters",
+            INVALID_INPUT_SK_CODEC_RESULT => "invalid input",
+            COULD_NOT_REWIND_SK_CODEC_RESULT => "could not rewind",
+            INTERNAL_ERROR_SK_CODEC_RESULT => "internal error",
+            UNIMPLEMENTED_SK_CODEC_RESULT => "unimplemented",
+        })
+    }
+}
+impl std::error::Error for CodecResult {}
+impl CodecResult {
+    /// converts the result code into a rust result. [CodecResult::SUCCESS_SK_CODEC_RESULT] is the only `Ok` value.
+    pub fn into_result(self) -> Result<(), CodecResult> {
+        match self {
+            CodecResult::SUCCESS_SK_CODEC_RESULT => Ok(()),
+            err => Err(err),
+        }
+    }
+}
+
+impl EncodedOrigin {
+    /// true if the image needs to be rotated by 90 or 270 degrees to be upright, which swaps its width and height.
+    pub fn swaps_width_height(self) -> bool {
+        self as u32 >= EncodedOrigin::LEFT_TOP_SK_ENCODED_ORIGIN as u32
+    }
+    /// The matrix which maps the encoded image of size `width` x `height` to its upright orientation.
+    pub fn to_matrix(self, width: i32, height: i32) -> Matrix {
+        use sk_encodedorigin_t::*;
+        let (w, h) = (width as f32, height as f32);
+        let [scale_x, skew_x, trans_x, skew_y, scale_y, trans_y] = match self {
+            TOP_LEFT_SK_ENCODED_ORIGIN => [1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+            TOP_RIGHT_SK_ENCODED_ORIGIN => [-1.0, 0.0, w, 0.0, 1.0, 0.0],
+            BOTTOM_RIGHT_SK_ENCODED_ORIGIN => [-1.0, 0.0, w, 0.0, -1.0, h],
+            BOTTOM_LEFT_SK_ENCODED_ORIGIN => [1.0, 0.0, 0.0, 0.0, -1.0, h],
+            LEFT_TOP_SK_ENCODED_ORIGIN => [0.0, 1.0, 0.0, 1.0, 0.0, 0.0],
+            RIGHT_TOP_SK_ENCODED_ORIGIN => [0.0, -1.0, h, 1.0, 0.0, 0.0],
+            RIGHT_BOTTOM_SK_ENCODED_ORIGIN => [0.0, -1.0, h, -1.0, 0.0, w],
+            LEFT_BOTTOM_SK_ENCODED_ORIGIN => [0.0, 1.0, 0.0, -1.0, 0.0, w],
+        };
+        Matrix {
+            scaleX: scale_x,
+            skewX: skew_x,
+            transX: trans_x,
+            skewY: skew_y,
+            scaleY: scale_y,
+            transY: trans_y,
+            ..Matrix::IDENTITY
+        }
+    }
+}
+
+/// Safe version of [CodecOptions]. The subset is stored inline, instead of behind a raw pointer.
+#[derive(Debug, Clone, Copy)]
+pub struct DecodeOptions {
+    /// If [CodecZeroInitialized::YES_SK_CODEC_ZERO_INITIALIZED], the codec may skip writing zeroes into the destination.
+    pub zero_initialized: CodecZeroInitialized,
+    /// Decode only this rect (in the coordinates of the full sized image). Use [Codec::get_valid_subset] to check support.
+    pub subset: Option<IRect>,
+    /// The frame to decode, for animated images.
+    pub frame_index: i32,
+    /// A frame which is already decoded into the destination, and which `frame_index` depends on.
+    pub prior_frame: Option<i32>,
+}
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        Self {
+            zero_initialized: CodecZeroInitialized::NO_SK_CODEC_ZERO_INITIALIZED,
+            subset: None,
+            frame_index: 0,
+            prior_frame: None,
+        }
+    }
+}
+impl DecodeOptions {
+    /// creates the native options struct. The subset pointer points into `subset`, so it must outlive the returned value.
+    pub(crate) fn as_native(&self, subset: &mut Option<IRect>) -> CodecOptions {
+        *subset = self.subset;
+        CodecOptions {
+            fZeroInitialized: self.zero_initialized,
+            fSubset: subset
+                .as_mut()
+                .map(|s| s.as_ptr_mut())
+                .unwrap_or(std::ptr::null_mut()),
+            fFrameIndex: self.frame_index,
+            fPriorFrame: self.prior_frame.unwrap_or(-1),
+        }
+    }
+}
+
+/// Decodes encoded images (png, jpeg, webp, gif etc..).
+///
+/// Unlike [Image::new_from_encoded], this lets you inspect the header (dimensions, format, origin) without decoding,
+/// and decode at a smaller scale or only a subset of the image.
+///
+/// The lifetime is tied to the source of encoded bytes (eg: [crate::stream::MemoryStream] with borrowed data).
+#[derive(Debug)]
+pub struct Codec<'a> {
+    pub(crate) inner: *mut sk_codec_t,
+    phantom: PhantomData<&'a [u8]>,
+}
+impl<'a> Drop for Codec<'a> {
+    fn drop(&mut self) {
+        unsafe { sk_codec_destroy(self.inner) }
+    }
+}
+#[cfg(feature = "unsafe_send")]
+unsafe impl<'a> Send for Codec<'a> {}
+impl Codec<'static> {
+    /// The codec keeps a reference to `data`. returns None if the format is not recognized.
+    pub fn new_from_data(data: &SkiaData) -> Option<Self> {
+        let inner = unsafe { sk_codec_new_from_data(data.as_ptr() as _) };
+        (!inner.is_null()).then_some(Self {
+            inner,
+            phantom: PhantomData,
+        })
+    }
+}
+impl<'a> Codec<'a> {
+    /// The minimum number of bytes that should be buffered in the stream, for the codec to recognize the format.
+    pub fn min_buffered_bytes_needed() -> usize {
+        unsafe { sk_codec_min_buffered_bytes_needed() }
+    }
+    /// The codec takes ownership of the stream (even on failure).
+    pub fn new_from_stream(stream: impl Stream + 'a) -> Result<Self, CodecResult> {
+        let mut result = CodecResult::SUCCESS_SK_CODEC_RESULT;
+        let inner =
+            unsafe { sk_codec_new_from_stream(stream.into_owned_stream_ptr(), &mut result) };
+        if inner.is_null() {
+            Err(match result {
+                // don't report success for a null codec
+                CodecResult::SUCCESS_SK_CODEC_RESULT => CodecResult::INTERNAL_ERROR_SK_CODEC_RESULT,
+                err => err,
+            })
+        } else {
+            Ok(Self {
+                inner,
+                phantom: PhantomData,
+            })
+        }
+    }
+    /// The dimensions, color type etc.. of the encoded image. Reading this doesn't decode any pixels.
+    pub fn get_info(&self) -> ImageInfo {
+        let mut info = ImageInfo::default();
+        unsafe { sk_codec_get_info(self.inner, info.as_ptr_mut()) };
+        info
+    }
+    pub fn get_origin(&self) -> EncodedOrigin {
+        unsafe { sk_codec_get_origin(self.inner) }
+    }
+    pub fn get_encoded_format(&self) -> EncodedImageFormat {
+        unsafe { sk_codec_get_encoded_format(self.inner) }
+    }
+    /// The closest dimensions to `desired_scale` (eg: 0.5 for half size) that the codec can decode to natively.
+    pub fn get_scaled_dimensions(&self, desired_scale: f32) -> ISize {
+        let mut size = ISize { w: 0, h: 0 };
+        unsafe { sk_codec_get_scaled_dimensions(self.inner, desired_scale, size.as_ptr_mut()) };
+        size
+    }
+    /// returns the closest subset to `desired` which the codec can decode. None, if subset decoding is not supported.
+    pub fn get_valid_subset(&self, mut desired: IRect) -> Option<IRect> {
+        unsafe { sk_codec_get_valid_subset(self.inner, desired.as_ptr_mut()).then_some(desired) }
+    }
+    /// The order in which rows are written by incremental and scanline decoding.
+    pub fn get_scanline_order(&self) -> CodecScanlineOrder {
+        unsafe { sk_codec_get_scanline_order(self.inner) }
+    }
+    /// Starts decoding into `pixels`, without requiring all the encoded bytes to be available.
+    /// Call [IncrementalDecode::decode] to actually decode rows (and call it again after the stream has more bytes).
+    ///
+    /// returns [CodecResult::UNIMPLEMENTED_SK_CODEC_RESULT] if the format doesn't support incremental decoding.
+    /// will panic if `pixels` is too small for `info` and `row_bytes`.
+    pub fn start_incremental_decode<'p>(
+        &'p mut self,
+        info: &ImageInfo,
+        pixels: &'p mut [u8],
+        row_bytes: usize,
+        options: &DecodeOptions,
+    ) -> Result<IncrementalDecode<'p, 'a>, CodecResult> {
+        assert!(row_bytes >= info.min_row_bytes(), "invalid row bytes value");
+        assert!(
+            pixels.len() >= info.compute_byte_size(row_bytes),
+            "pixel buffer is too small"
+        );
+        // skia keeps a copy of the options (including the subset pointer), so the subset needs a stable address
+        let mut subset = Box::new(None);
+        let native_options = options.as_native(&mut subset);
+        unsafe {
+            sk_codec_start_incremental_decode(
+                self.inner,
+                info.as_ptr(),
+                pixels.as_mut_ptr() as _,
+                row_bytes,
+                native_options.as_ptr(),
+            )
+        }
+        .into_result()?;
+        Ok(IncrementalDecode {
+            height: info.get_height(),
+            codec: self,
+            _subset: subset,
+            _pixels: PhantomData,
+            rows_decoded: 0,
+        })
+    }
+    /// Starts decoding rows one batch at a time (see [ScanlineDecoder]), so the full image never needs to be in memory.
+    ///
+    /// `info` can have smaller dimensions than [Self::get_info], if [Self::get_scaled_dimensions] supports them.
+    /// `options.subset` can only restrict the columns (left and right). Use [ScanlineDecoder::skip_scanlines] to skip rows.
+    pub fn start_scanline_decode(
+        &mut self,
+        info: ImageInfo,
+        options: &DecodeOptions,
+    ) -> Result<ScanlineDecoder<'_, 'a>, CodecResult> {
+        // skia keeps a copy of the options (including the subset pointer), so the subset needs a stable address
+        let mut subset = Box::new(None);
+        let native_options = options.as_native(&mut subset);
+        unsafe {
+            sk_codec_start_scanline_decode(self.inner, info.as_ptr(), native_options.as_ptr())
+        }
+        .into_result()?;
+        // with a subset, the rows are only as wide as the subset
+        let width = subset
+            .map(|subset| subset.right - subset.left)
+            .unwrap_or(info.get_width());
+        Ok(ScanlineDecoder {
+            codec: self,
+            row_len: width.max(0) as usize * info.bytes_per_pixel() as usize,
+            info,
+            _subset: subset,
+            next_input_row: 0,
+        })
+    }
+    /// The number of frames. This is 1 for still images.
+    ///
+    /// For incomplete data, this only counts the frames received so far.
+    pub fn get_frame_count(&self) -> usize {
+        unsafe { sk_codec_get_frame_count(self.inner).max(0) as usize }
+    }
+    /// returns None for out of bounds `index` or if the image is not animated.
+    pub fn get_frame_info_for_index(&self, index: usize) -> Option<CodecFrameInfo> {
+        let mut frame_info = std::mem::MaybeUninit::<CodecFrameInfo>::zeroed();
+        unsafe {
+            sk_codec_get_frame_info_for_index(self.inner, index as _, frame_info.as_mut_ptr())
+                .then(|| frame_info.assume_init())
+        }
+    }
+    /// info of all frames. This is empty for still images.
+    pub fn get_frame_infos(&self) -> Vec<CodecFrameInfo> {
+        let count = self.get_frame_count();
+        // still images don't have frame info
+        if self.get_frame_info_for_index(0).is_none() {
+            return vec![];
+        }
+        let mut infos = Vec::<CodecFrameInfo>::with_capacity(count);
+        unsafe {
+            sk_codec_get_frame_info(self.inner, infos.as_mut_ptr());
+            infos.set_len(count);
+        }
+        infos
+    }
+    /// The number of times to repeat the animation after playing it once.
+    /// None, if it should repeat forever.
+    pub fn get_repetition_count(&self) -> Option<u32> {
+        match unsafe { sk_codec_get_repetition_count(self.inner) } {
+            // kRepetitionCountInfinite
+            -1 => None,
+            count => Some(count.max(0) as u32),
+        }
+    }
+    /// Decodes into `pixels` with the layout described by `info` and `row_bytes`.
+    ///
+    /// `info` may use a different color type or smaller (scaled) dimensions than [Self::get_info].
+    /// On [CodecResult::INCOMPLETE_INPUT_SK_CODEC_RESULT], the pixels which could be decoded are still written.
+    ///
+    /// will panic if `pixels` is too small for `info` and `row_bytes`.
+    pub fn get_pixels(
+        &mut self,
+        info: &ImageInfo,
+        pixels: &mut [u8],
+        row_bytes: usize,
+        options: &DecodeOptions,
+    ) -> Result<(), CodecResult> {
+        assert!(row_bytes >= info.min_row_bytes(), "invalid row bytes value");
+        assert!(
+            pixels.len() >= info.compute_byte_size(row_bytes),
+            "pixel buffer is too small"
+        );
+        let mut subset = None;
+        let options = options.as_native(&mut subset);
+        unsafe {
+            sk_codec_get_pixels(
+                self.inner,
+                info.as_ptr(),
+                pixels.as_mut_ptr() as _,
+                row_bytes,
+                options.as_ptr(),
+            )
+        }
+        .into_result()
+    }
+    /// Decodes into the pixels of `pixmap`, using its info and row bytes.
+    /// returns [CodecResult::INVALID_PARAMETERS_SK_CODEC_RESULT] if the pixmap doesn't have any pixels.
+    pub fn decode_into_pixmap<T>(
+        &mut self,
+        pixmap: &mut PixMap<'_, T>,
+        options: &DecodeOptions,
+    ) -> Result<(), CodecResult> {
+        let mut info = ImageInfo::default();
+        let (pixels, row_bytes) = unsafe {
+            sk_pixmap_get_info(pixmap.inner, info.as_ptr_mut());
+            (
+                sk_pixmap_get_writable_addr(pixmap.inner),
+                sk_pixmap_get_row_bytes(pixmap.inner),
+            )
+        };
+        if pixels.is_null() {
+            return Err(CodecResult::INVALID_PARAMETERS_SK_CODEC_RESULT);
+        }
+        let mut subset = None;
+        let options = options.as_native(&mut subset);
+        unsafe {
+            sk_codec_get_pixels(
+                self.inner,
+                info.as_ptr(),
+                pixels,
+                row_bytes,
+                options.as_ptr(),
+            )
+        }
+        .into_result()
+    }
+    /// Decodes into a new raster [Image].
+    ///
+    /// `desired_scale` is snapped to the closest scale supported by the codec (see [Self::get_scaled_dimensions]).
+    /// `subset` is in the coordinates of the full sized image, and is snapped to the closest valid subset (see [Self::get_valid_subset]).
+    /// returns [CodecResult::UNIMPLEMENTED_SK_CODEC_RESULT] if the codec can't decode subsets.
+    pub fn decode_image(
+        &mut self,
+        desired_scale: f32,
+        subset: Option<IRect>,
+    ) -> Result<Image, CodecResult> {
+        let mut options = DecodeOptions::default();
+        let mut info = self.get_info();
+        let dimensions = match subset {
+            Some(subset) => {
+                let subset = self
+                    .get_valid_subset(subset)
+                    .ok_or(CodecResult::UNIMPLEMENTED_SK_CODEC_RESULT)?;
+                options.subset = Some(subset);
+                let scale = |len: i32| ((len as f32 * desired_scale).round() as i32).max(1);
+                ISize {
+                    w: scale(subset.right - subset.left),
+                    h: scale(subset.bottom - subset.top),
+                }
+            }
+            None => self.get_scaled_dimensions(desired_scale),
+        };
+        info.set_width(dimensions.w);
+        info.set_height(dimensions.h);
+        self.decode_to_bitmap(&info, &options).and_then(|bitmap| {
+            Image::new_from_bitmap(&bitmap).ok_or(CodecResult::INTERNAL_ERROR_SK_CODEC_RESULT)
+        })
+    }
+    /// Like [Self::decode_image], but rotates/flips the result according to [Self::get_origin] (eg: EXIF orientation of photos).
+    ///
+    /// Also returns the matrix which was applied, which maps the decoded coordinates to the upright image.
+    /// Use its inverse to map coordinates in the upright image back to the encoded image.
+    ///
+    /// Fails with `INTERNAL_ERROR` if the upright image can't be allocated (eg: a huge image).
+    pub fn decode_image_oriented(
+        &mut self,
+        desired_scale: f32,
+    ) -> Result<(Image, Matrix), CodecResult> {
+        let image = self.decode_image(desired_scale, None)?;
+        let origin = self.get_origin();
+        if origin == EncodedOrigin::TOP_LEFT_SK_ENCODED_ORIGIN {
+            return Ok((image, Matrix::IDENTITY));
+        }
+        let (width, height) = (image.get_width(), image.get_height());
+        let matrix = origin.to_matrix(width, height);
+        let mut info = ImageInfo::default();
+        if origin.swaps_width_height() {
+            info.set_width(height);
+            info.set_height(width);
+        } else {
+            info.set_width(width);
+            info.set_height(height);
+        }
+        info.set_color_type(image.get_color_type());
+        info.set_alpha_type(image.get_alpha_type());
+        info.set_colorspace(image.get_color_space());
+        let props = SurfaceProps::new(0, PixelGeometry::UNKNOWN_SK_PIXELGEOMETRY);
+        let mut surface = unsafe {
+            Surface::try_from_owned_ptr(sk_surface_new_raster(info.as_ptr(), 0, props.as_ptr()))
+        }
+        .ok_or(CodecResult::INTERNAL_ERROR_SK_CODEC_RESULT)?;
+        {
+            let mut surface_canvas = surface.get_canvas();
+            let canvas = surface_canvas.as_mut();
+            canvas.concat(&matrix.into());
+            let mut paint = Paint::default();
+            paint.set_blendmode(BlendMode::SRC_SK_BLENDMODE);
+            canvas.draw_image(&image, 0.0, 0.0, &SamplingOptions::NEAREST, &paint);
+        }
+        Ok((surface.new_image_snapshot(), matrix))
+    }
+    /// allocates a bitmap for `info` and decodes into it. The bitmap is marked immutable, so that images can share its pixels.
+    pub(crate) fn decode_to_bitmap(
+        &mut self,
+        info: &ImageInfo,
+        options: &DecodeOptions,
+    ) -> Result<BitMap, CodecResult> {
+        let mut bitmap = BitMap::default();
+        if !bitmap.try_alloc_pixels(info, 0) {
+            return Err(CodecResult::INTERNAL_ERROR_SK_CODEC_RESULT);
+        }
+        let row_bytes = bitmap.get_row_bytes();
+        self.get_pixels(info, bitmap.get_pixels(), row_bytes, options)?;
+        bitmap.set_immutable();
+        Ok(bitmap)
+    }
+}
+
+/// A frame composited by [AnimatedImage].
+pub struct AnimatedFrame {
+    pub index: usize,
+    pub image: Image,
+    /// how long this frame should be displayed.
+    pub duration: Duration,
+}
+/// Decodes frames of animated images (gif, webp etc..) into a full sized canvas,
+/// taking care of the required frames, disposal methods and blending between frames.
+///
+/// A single bitmap is reused for all frames, and the previously composited frame is reused as a starting point
+/// for the next frame when possible. So, decoding frames in order is cheap, while seeking may decode older frames.
+///
+/// Still images are treated as an animation with a single frame of zero duration.
+pub struct AnimatedImage<'a> {
+    codec: Codec<'a>,
+    info: ImageInfo,
+    frames: Vec<CodecFrameInfo>,
+    repetition_count: Option<u32>,
+    bitmap: BitMap,
+    /// the frame currently composited in `bitmap`
+    decoded_frame: Option<usize>,
+    /// the next frame to yield from the iterator
+    next_frame: usize,
+    /// number of completed plays of the whole animation
+    plays: u32,
+    /// set after the iterator yields an error
+    finished: bool,
+}
+impl<'a> AnimatedImage<'a> {
+    pub fn new(codec: Codec<'a>) -> Result<Self, CodecResult> {
+        let mut info = codec.get_info();
+        // frames are composited on top of each other, so they need to be premultiplied
+        if info.get_alpha_type() == AlphaType::UNPREMUL_SK_ALPHATYPE {
+            info.set_alpha_type(AlphaType::PREMUL_SK_ALPHATYPE);
+        }
+        let mut frames = codec.get_frame_infos();
+        if frames.is_empty() {
+            frames.push(CodecFrameInfo {
+                fRequiredFrame: -1,
+                fDuration: 0,
+                fFullyReceived: true,
+                fAlphaType: info.get_alpha_type(),
+                fHasAlphaWithinBounds: info.get_alpha_type() != AlphaType::OPAQUE_SK_ALPHATYPE,
+                fDisposalMethod:
+                    CodecanimationDisposalmethod::KEEP_SK_CODEC_ANIMATION_DISPOSAL_METHOD,
+                fBlend: CodecanimationBlend::SRC_OVER_SK_CODEC_ANIMATION_BLEND,
+                fFrameRect: IRect::new(0, 0, info.get_width(), info.get_height()),
+            });
+        }
+        let mut bitmap = BitMap::default();
+        if !bitmap.try_alloc_pixels(&info, 0) {
+            return Err(CodecResult::INTERNAL_ERROR_SK_CODEC_RESULT);
+        }
+        Ok(Self {
+            repetition_count: codec.get_repetition_count(),
+            codec,
+            info,
+            frames,
+            bitmap,
+            decoded_frame: None,
+            next_frame: 0,
+            plays: 0,
+            finished: false,
+        })
+    }
+    pub fn get_info(&self) -> &ImageInfo {
+        &self.info
+    }
+    pub fn get_frame_count(&self) -> usize {
+        self.frames.len()
+    }
+    pub fn get_frame_info(&self, index: usize) -> Option<&CodecFrameInfo> {
+        self.frames.get(index)
+    }
+    /// returns zero for out of bounds index
+    pub fn get_frame_duration(&self, index: usize) -> Duration {
+        self.frames
+            .get(index)
+            .map(|f| Duration::from_millis(f.fDuration.max(0) as u64))
+            .unwrap_or_default()
+    }
+    /// The number of times to repeat the animation after playing it once. None, if it repeats forever.
+    pub fn get_repetition_count(&self) -> Option<u32> {
+        self.repetition_count
+    }
+    /// Composites the frame at `index` into the internal bitmap and returns it.
+    /// The bitmap is reused (overwritten) by the next call, so use [Image::new_from_bitmap] to keep a copy.
+    pub fn decode_frame(&mut self, index: usize) -> Result<&BitMap, CodecResult> {
+        let frame = self
+            .frames
+            .get(index)
+            .ok_or(CodecResult::INVALID_PARAMETERS_SK_CODEC_RESULT)?;
+        if self.decoded_frame != Some(index) {
+            // we can start from the currently composited frame, if it is in the range [required_frame, index)
+            // and is not going to be restored to a previous frame. Otherwise, codec will decode the required frames itself.
+            let prior_frame = self.decoded_frame.filter(|&prior| {
+                prior < index
+                    && prior as i32 >= frame.fRequiredFrame
+                    && self.frames[prior].fDisposalMethod
+                        != CodecanimationDisposalmethod::RESTORE_PREVIOUS_SK_CODEC_ANIMATION_DISPOSAL_METHOD
+            });
+            let options = DecodeOptions {
+                frame_index: index as _,
+                prior_frame: prior_frame.map(|prior| prior as _),
+                ..Default::default()
+            };
+            // if decoding fails midway, the bitmap contents are garbage
+            self.decoded_frame = None;
+            let row_bytes = self.bitmap.get_row_bytes();
+            match self
+                .codec
+                .get_pixels(&self.info, self.bitmap.get_pixels(), row_bytes, &options)
+            {
+                // partially received frames are still shown, like browsers do.
+                Ok(()) | Err(CodecResult::INCOMPLETE_INPUT_SK_CODEC_RESULT) => {}
+                Err(e) => return Err(e),
+            }
+            self.bitmap.notify_pixels_changed();
+            self.decoded_frame = Some(index);
+        }
+        Ok(&self.bitmap)
+    }
+    /// Composites the frame at `index` and copies it into a new [Image].
+    pub fn get_frame(&mut self, index: usize) -> Result<AnimatedFrame, CodecResult> {
+        let bitmap = self.decode_frame(index)?;
+        let image =
+            Image::new_from_bitmap(bitmap).ok_or(CodecResult::INTERNAL_ERROR_SK_CODEC_RESULT)?;
+        Ok(AnimatedFrame {
+            index,
+            image,
+            duration: self.get_frame_duration(index),
+        })
+    }
+    /// Restarts the iterator from the first frame and resets the loop count.
+    pub fn rewind(&mut self) {
+        self.next_frame = 0;
+        self.plays = 0;
+        self.finished = false;
+    }
+}
+/// Yields the frames in order, repeating the animation as many times as [AnimatedImage::get_repetition_count] says.
+/// Stops after the first error.
+impl<'a> Iterator for AnimatedImage<'a> {
+    type Item = Result<AnimatedFrame, CodecResult>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        if self.next_frame >= self.frames.len() {
+            match self.repetition_count {
+                Some(count) if self.plays >= count => return None,
+                _ => {
+                    self.next_frame = 0;
+                    self.plays = self.plays.saturating_add(1);
+                }
+            }
+        }
+        let frame = self.get_frame(self.next_frame);
+        self.next_frame += 1;
+        self.finished = frame.is_err();
+        Some(frame)
+    }
+}
+
+/// An incremental decode started by [Codec::start_incremental_decode].
+///
+/// It borrows the codec and the destination pixels, because skia keeps writing into them on every [Self::decode].
+pub struct IncrementalDecode<'p, 'a> {
+    codec: &'p mut Codec<'a>,
+    height: i32,
+    _subset: Box<Option<IRect>>,
+    _pixels: PhantomData<&'p mut [u8]>,
+    rows_decoded: i32,
+}
+impl<'p, 'a> IncrementalDecode<'p, 'a> {
+    /// Decodes as many rows as the bytes available in the stream allow.
+    ///
+    /// returns Ok(true) once the image is fully decoded, and Ok(false) if the stream ran out of bytes.
+    /// In the latter case, call this again after more bytes arrive to resume from where it stopped.
+    pub fn decode(&mut self) -> Result<bool, CodecResult> {
+        let mut rows_decoded = 0;
+        match unsafe { sk_codec_incremental_decode(self.codec.inner, &mut rows_decoded) } {
+            CodecResult::SUCCESS_SK_CODEC_RESULT => {
+                self.rows_decoded = self.height;
+                Ok(true)
+            }
+            CodecResult::INCOMPLETE_INPUT_SK_CODEC_RESULT => {
+                self.rows_decoded = rows_decoded;
+                Ok(false)
+            }
+            err => Err(err),
+        }
+    }
+    pub fn get_rows_decoded(&self) -> i32 {
+        self.rows_decoded
+    }
+    /// The rows of the destination which contain decoded pixels so far.
+    /// The rest of the rows are left untouched (or zero initialized), so they are not worth showing.
+    pub fn get_valid_rows(&self) -> Range<i32> {
+        valid_rows(
+            self.codec.get_scanline_order(),
+            self.rows_decoded,
+            self.height,
+        )
+    }
+}
+fn valid_rows(order: CodecScanlineOrder, rows_decoded: i32, height: i32) -> Range<i32> {
+    match order {
+        CodecScanlineOrder::TOP_DOWN_SK_CODEC_SCANLINE_ORDER => 0..rows_decoded,
+        CodecScanlineOrder::BOTTOM_UP_SK_CODEC_SCANLINE_ORDER => (height - rows_decoded)..height,
+    }
+}
+
+/// Progressive decoding of an image whose encoded bytes arrive in chunks (eg: over network), like browsers do.
+///
+/// Keep calling [Self::append] with new bytes. Once the header is available, the decoder allocates a bitmap
+/// and decodes rows into it as their bytes arrive. [Self::get_bitmap] can be drawn at any point as a preview.
+///
+/// Only some formats (eg: png, gif) support incremental decoding. Others report [CodecResult::UNIMPLEMENTED_SK_CODEC_RESULT].
+pub struct IncrementalDecoder {
+    // the codec borrows `data` and `retired`, so it must be declared (and dropped) before them.
+    codec: Option<Codec<'static>>,
+    /// the memory stream owned by codec. We keep the pointer, so that we can point it at the grown buffer.
+    stream: *mut sk_stream_memorystream_t,
+    info: ImageInfo,
+    bitmap: BitMap,
+    started: bool,
+    complete: bool,
+    rows_decoded: i32,
+    data: Vec<u8>,
+    /// older buffers which we outgrew. Codecs might still hold pointers into them, so they live as long as the codec.
+    retired: Vec<Vec<u8>>,
+}
+impl Default for IncrementalDecoder {
+    fn default() -> Self {
+        Self {
+            codec: None,
+            stream: std::ptr::null_mut(),
+            info: ImageInfo::default(),
+            bitmap: BitMap::default(),
+            started: false,
+            complete: false,
+            rows_decoded: 0,
+            data: Vec::new(),
+            retired: Vec::new(),
+        }
+    }
+}
+impl IncrementalDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Adds the next chunk of encoded bytes and decodes as much as possible.
+    ///
+    /// returns Ok(true) once the image is fully decoded, Ok(false) if more bytes are needed.
+    pub fn append(&mut self, bytes: &[u8]) -> Result<bool, CodecResult> {
+        if self.complete {
+            return Ok(true);
+        }
+        self.extend_data(bytes);
+        if self.codec.is_none() {
+            match self.create_codec() {
+                Ok(codec) => self.codec = Some(codec),
+                // not enough bytes to read the header yet
+                Err(CodecResult::INCOMPLETE_INPUT_SK_CODEC_RESULT) => return Ok(false),
+                Err(e) => return Err(e),
+            }
+        } else {
+            unsafe {
+                let stream = self.stream as *mut sk_stream_t;
+                // set_memory rewinds the stream, so we restore the position after pointing it at the new bytes
+                let position = sk_stream_get_position(stream);
+                sk_memorystream_set_memory(
+                    self.stream,
+                    self.data.as_ptr() as _,
+                    self.data.len(),
+                    false,
+                );
+                sk_stream_seek(stream, position);
+            }
+        }
+        let codec = self.codec.as_mut().expect("codec must be created by now");
+        if !self.started {
+            let mut info = codec.get_info();
+            if info.get_alpha_type() == AlphaType::UNPREMUL_SK_ALPHATYPE {
+                info.set_alpha_type(AlphaType::PREMUL_SK_ALPHATYPE);
+            }
+            if !self.bitmap.try_alloc_pixels(&info, 0) {
+                return Err(CodecResult::INTERNAL_ERROR_SK_CODEC_RESULT);
+            }
+            self.bitmap.erase(crate::Color::TRANSPARENT);
+            let row_bytes = self.bitmap.get_row_bytes();
+            let options = DecodeOptions {
+                zero_initialized: CodecZeroInitialized::YES_SK_CODEC_ZERO_INITIALIZED,
+                ..Default::default()
+            };
+            let mut subset = None;
+            let native_options = options.as_native(&mut subset);
+            // the bitmap's pixels are never reallocated, so skia can keep writing into them across append calls
+            let result = unsafe {
+                sk_codec_start_incremental_decode(
+                    codec.inner,
+                    info.as_ptr(),
+                    self.bitmap.get_pixels().as_mut_ptr() as _,
+                    row_bytes,
+                    native_options.as_ptr(),
+                )
+            };
+            match result {
+                CodecResult::SUCCESS_SK_CODEC_RESULT => {}
+                // not enough data to start yet (eg: the rest of the header). try again on the next append.
+                CodecResult::INCOMPLETE_INPUT_SK_CODEC_RESULT => return Ok(false),
+                err => return Err(err),
+            }
+            self.info = info;
+            self.started = true;
+        }
+        let mut rows_decoded = 0;
+        let result = unsafe { sk_codec_incremental_decode(codec.inner, &mut rows_decoded) };
+        self.bitmap.notify_pixels_changed();
+        match result {
+            CodecResult::SUCCESS_SK_CODEC_RESULT => {
+                self.rows_decoded = self.info.get_height();
+                self.complete = true;
+                Ok(true)
+            }
+            CodecResult::INCOMPLETE_INPUT_SK_CODEC_RESULT => {
+                self.rows_decoded = rows_decoded;
+                Ok(false)
+            }
+            err => Err(err),
+        }
+    }
+    /// appends bytes to `data`. If we need to grow, we retire the old buffer instead of freeing it.
+    fn extend_data(&mut self, bytes: &[u8]) {
+        let required = self.data.len() + bytes.len();
+        if required > self.data.capacity() && self.codec.is_some() {
+            let mut data = Vec::with_capacity(required.max(self.data.capacity() * 2));
+            data.extend_from_slice(&self.data);
+            self.retired.push(std::mem::replace(&mut self.data, data));
+        }
+        self.data.extend_from_slice(bytes);
+    }
+    fn create_codec(&mut self) -> Result<Codec<'static>, CodecResult> {
+        unsafe {
+            // Safety: data outlives codec. see the field comments
+            let stream = MemoryStream::<'static>::from_owned_ptr(sk_memorystream_new_with_data(
+                self.data.as_ptr() as _,
+                self.data.len(),
+                false,
+            ))
+            .ok_or(CodecResult::INTERNAL_ERROR_SK_CODEC_RESULT)?;
+            let stream_ptr = stream.inner;
+            let codec = Codec::new_from_stream(stream)?;
+            self.stream = stream_ptr;
+            Ok(codec)
+        }
+    }
+    /// None until enough bytes have arrived to read the header
+    pub fn get_codec(&self) -> Option<&Codec<'static>> {
+        self.codec.as_ref()
+    }
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+    pub fn get_rows_decoded(&self) -> i32 {
+        self.rows_decoded
+    }
+    /// The rows of the bitmap which contain decoded pixels so far.
+    pub fn get_valid_rows(&self) -> Range<i32> {
+        match &self.codec {
+            Some(codec) if self.started => valid_rows(
+                codec.get_scanline_order(),
+                self.rows_decoded,
+                self.info.get_height(),
+            ),
+            _ => 0..0,
+        }
+    }
+    /// The partially decoded image. Rows outside [Self::get_valid_rows] are transparent.
+    /// None, until decoding has started.
+    pub fn get_bitmap(&self) -> Option<&BitMap> {
+        self.started.then_some(&self.bitmap)
+    }
+    /// copies the partially decoded pixels into a new image, which can be used as a preview.
+    pub fn snapshot_image(&self) -> Option<Image> {
+        self.get_bitmap().and_then(Image::new_from_bitmap)
+    }
+}
+
+/// A range of rows decoded by [ScanlineDecoder::next_batch].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowBatch {
+    /// The rows of the output image which are in the buffer, in top-down order.
+    pub rows: Range<i32>,
+    /// How many of those rows were actually decoded. The rest were filled, because the input is incomplete.
+    pub rows_decoded: i32,
+}
+/// Decodes an image a few rows at a time into a caller provided buffer. Created by [Codec::start_scanline_decode].
+///
+/// Useful for huge images, where the decoded pixels wouldn't fit in memory.
+/// eg: downscale (or tile) each batch of rows as it is decoded, and then reuse the buffer for the next batch.
+pub struct ScanlineDecoder<'c, 'a> {
+    codec: &'c mut Codec<'a>,
+    info: ImageInfo,
+    /// the number of bytes in a decoded row (could be smaller than info's row if we are decoding a subset)
+    row_len: usize,
+    _subset: Box<Option<IRect>>,
+    /// rows are counted in the order the codec decodes them. see [CodecScanlineOrder]
+    next_input_row: i32,
+}
+impl<'c, 'a> ScanlineDecoder<'c, 'a> {
+    pub fn get_info(&self) -> &ImageInfo {
+        &self.info
+    }
+    /// The minimum row bytes of the destination buffer.
+    pub fn get_min_row_bytes(&self) -> usize {
+        self.row_len
+    }
+    pub fn get_scanline_order(&self) -> CodecScanlineOrder {
+        self.codec.get_scanline_order()
+    }
+    /// The number of rows left to decode (or skip)
+    pub fn get_remaining_rows(&self) -> i32 {
+        self.info.get_height() - self.next_input_row
+    }
+    /// The row in the output image which will be decoded next.
+    /// For bottom up images, this starts at the last row and moves upwards.
+    pub fn next_scanline(&self) -> i32 {
+        unsafe { sk_codec_next_scanline(self.codec.inner) }
+    }
+    /// The row in the output image that the `input_scanline`th decoded row belongs to.
+    pub fn output_scanline(&self, input_scanline: i32) -> i32 {
+        unsafe { sk_codec_output_scanline(self.codec.inner, input_scanline) }
+    }
+    /// Decodes the next `count` rows into `dst` in decoding order, and returns the number of rows actually decoded.
+    /// If the input is incomplete, the remaining rows are still filled.
+    ///
+    /// will panic if `dst` can't hold `count` rows of `row_bytes` or if `row_bytes` is smaller than [Self::get_min_row_bytes].
+    pub fn get_scanlines(&mut self, dst: &mut [u8], count: i32, row_bytes: usize) -> i32 {
+        assert!(row_bytes >= self.row_len, "invalid row bytes value");
+        let count = count.clamp(0, self.get_remaining_rows());
+        if count == 0 {
+            return 0;
+        }
+        assert!(
+            dst.len() >= (count as usize - 1) * row_bytes + self.row_len,
+            "buffer is too small"
+        );
+        let decoded = unsafe {
+            sk_codec_get_scanlines(self.codec.inner, dst.as_mut_ptr() as _, count, row_bytes)
+        };
+        self.next_input_row += count;
+        decoded
+    }
+    /// Skips the next `count` rows without writing them anywhere. returns false on failure (eg: incomplete input)
+    pub fn skip_scanlines(&mut self, count: i32) -> bool {
+        let count = count.clamp(0, self.get_remaining_rows());
+        let success = unsafe { sk_codec_skip_scanlines(self.codec.inner, count) };
+        if success {
+            self.next_input_row += count;
+        }
+        success
+    }
+    /// Decodes up to `max_rows` rows into `dst`. Unlike [Self::get_scanlines], the rows in `dst` are always
+    /// in top-down order, even for bottom up images. returns None after all rows are decoded.
+    ///
+    /// will panic if `dst` can't hold `max_rows` rows of `row_bytes`.
+    pub fn next_batch(
+        &mut self,
+        dst: &mut [u8],
+        max_rows: i32,
+        row_bytes: usize,
+    ) -> Option<RowBatch> {
+        let count = max_rows.min(self.get_remaining_rows());
+        if count <= 0 {
+            return None;
+        }
+        let first_input_row = self.next_input_row;
+        let rows_decoded = self.get_scanlines(dst, count, row_bytes);
+        let first = self.output_scanline(first_input_row);
+        let last = self.output_scanline(first_input_row + count - 1);
+        if first > last {
+            // bottom up. reverse the rows in place to make them top down
+            for i in 0..(count as usize / 2) {
+                let j = count as usize - 1 - i;
+                let (upper, lower) = dst.split_at_mut(j * row_bytes);
+                upper[i * row_bytes..][..self.row_len].swap_with_slice(&mut lower[..self.row_len]);
+            }
+        }
+        Some(RowBatch {
+            rows: first.min(last)..(first.max(last) + 1),
+            rows_decoded,
+        })
+    }
+    /// Decodes the whole image in batches of `rows_per_batch` rows, calling `f` with each batch and its pixels.
+    /// Only a single batch worth of pixels is allocated, and it is reused for all batches.
+    pub fn for_each_batch(
+        &mut self,
+        rows_per_batch: i32,
+        mut f: impl FnMut(&RowBatch, &mut [u8], usize),
+    ) -> Result<(), CodecResult> {
+        if rows_per_batch <= 0 {
+            return Err(CodecResult::INVALID_PARAMETERS_SK_CODEC_RESULT);
+        }
+        let row_bytes = self.row_len;
+        let mut buffer = vec![0u8; row_bytes * rows_per_batch as usize];
+        while let Some(batch) = self.next_batch(&mut buffer, rows_per_batch, row_bytes) {
+            let rows = batch.rows.len();
+            f(&batch, &mut buffer[..rows * row_bytes], row_bytes);
+            if batch.rows_decoded < rows as i32 {
+                return Err(CodecResult::INCOMPLETE_INPUT_SK_CODEC_RESULT);
+            }
+        }
+        Ok(())
+    }
+}
@@ -233,6 +233,48 @@ impl Add for ColorspaceXyz {
     }
 }
 
+/// the linear transfer fn (`y = x`).
+impl Default for ColorspaceTransferFn {
+    fn default() -> Self {
+        Self {
+            fG: 1.0,
+            fA: 1.0,
+            fB: 0.0,
+            fC: 0.0,
+            fD: 0.0,
+            fE: 0.0,
+            fF: 0.0,
+        }
+    }
+}
+impl ColorspaceTransferFn {
+    pub fn set_srgb(&mut self) {
+        unsafe { sk_colorspace_transfer_fn_named_srgb(self.as_ptr_mut()) }
+    }
+    pub fn set_2dot2(&mut self) {
+        unsafe { sk_colorspace_transfer_fn_named_2dot2(self.as_ptr_mut()) }
+    }
+    pub fn set_linear(&mut self) {
+        unsafe { sk_colorspace_transfer_fn_named_linear(self.as_ptr_mut()) }
+    }
+    pub fn set_rec2020(&mut self) {
+        unsafe { sk_colorspace_transfer_fn_named_rec2020(self.as_ptr_mut()) }
+    }
+    pub fn set_pq(&mut self) {
+        unsafe { sk_colorspace_transfer_fn_named_pq(self.as_ptr_mut()) }
+    }
+    pub fn set_hlg(&mut self) {
+        unsafe { sk_colorspace_transfer_fn_named_hlg(self.as_ptr_mut()) }
+    }
+    pub fn eval(&self, x: f32) -> f32 {
+        unsafe { sk_colorspace_transfer_fn_eval(self.as_ptr(), x) }
+    }
+    pub fn invert(&self) -> Option<Self> {
+        let mut dst = Self::default();
+        unsafe { sk_colorspace_transfer_fn_invert(self.as_ptr(), dst.as_ptr_mut()).then_some(dst) }
+    }
+}
+
 crate::skia_wrapper!(
     nvrefcnt,
     ColorSpace,
@@ -328,7 +370,7 @@ impl ColorSpace {
     */
 }
 pub struct ICCProfile<'a> {
-    inner: *mut sk_colorspace_icc_profile_t,
+    pub(crate) inner: *mut sk_colorspace_icc_profile_t,
     phantom: PhantomData<&'a [u8]>,
 }
 impl<'a> Drop for ICCProfile<'a> {
@@ -0,0 +1,287 @@
+use crate::{bindings::sk_color_channel_t, filter::ColorFilter, ColorChannel};
+
+/// luminance weights used by [ColorMatrix::saturation] and [ColorMatrix::hue_rotate] (same as svg's `feColorMatrix`)
+const LUM_R: f32 = 0.213;
+const LUM_G: f32 = 0.715;
+const LUM_B: f32 = 0.072;
+
+/// A 4x5 row-major matrix that transforms unpremultiplied rgba colors.
+///
+/// Each row computes one output channel: `r' = m[0]*r + m[1]*g + m[2]*b + m[3]*a + m[4]`.
+/// The components and the translation (last column) are in `0..=1`.
+///
+/// ```rust,ignore
+/// let adjust = ColorMatrix::saturation(1.2)
+///     .post_concat(ColorMatrix::brightness(0.1))
+///     .post_concat(ColorMatrix::contrast(1.1));
+/// paint.set_colorfilter(Some(&mut adjust.into()));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorMatrix(pub [f32; 20]);
+impl Default for ColorMatrix {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+impl ColorMatrix {
+    pub const IDENTITY: Self = Self::scale(1.0, 1.0, 1.0, 1.0);
+
+    pub const fn scale(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self([
+            r, 0.0, 0.0, 0.0, 0.0, //
+            0.0, g, 0.0, 0.0, 0.0, //
+            0.0, 0.0, b, 0.0, 0.0, //
+            0.0, 0.0, 0.0, a, 0.0,
+        ])
+    }
+    /// adds `r`, `g`, `b` and `a` to the channels
+    pub const fn translate(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self([
+            1.0, 0.0, 0.0, 0.0, r, //
+            0.0, 1.0, 0.0, 0.0, g, //
+            0.0, 0.0, 1.0, 0.0, b, //
+            0.0, 0.0, 0.0, 1.0, a,
+        ])
+    }
+    /// 0 is grayscale, 1 is unchanged and values above 1 oversaturate.
+    pub fn saturation(s: f32) -> Self {
+        let (r, g, b) = (LUM_R * (1.0 - s), LUM_G * (1.0 - s), LUM_B * (1.0 - s));
+        Self([
+            r + s,
+            g,
+            b,
+            0.0,
+            0.0, //
+            r,
+            g + s,
+            b,
+            0.0,
+            0.0, //
+            r,
+            g,
+            b + s,
+            0.0,
+            0.0, //
+            0.0,
+            0.0,
+            0.0,
+            1.0,
+            0.0,
+        ])
+    }
+    /// rotates the hue by `degrees`, keeping the luminance.
+    pub fn hue_rotate(degrees: f32) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Self([
+            LUM_R + cos * (1.0 - LUM_R) - sin * LUM_R,
+            LUM_G - cos * LUM_G - sin * LUM_G,
+            LUM_B - cos * LUM_B + sin * (1.0 - LUM_B),
+            0.0,
+            0.0,
+            LUM_R - cos * LUM_R + sin * 0.143,
+            LUM_G + cos * (1.0 - LUM_G) + sin * 0.140,
+            LUM_B - cos * LUM_B - sin * 0.283,
+            0.0,
+            0.0,
+            LUM_R - cos * LUM_R - sin * (1.0 - LUM_R),
+            LUM_G - cos * LUM_G + sin * LUM_G,
+            LUM_B + cos * (1.0 - LUM_B) + sin * LUM_B,
+            0.0,
+            0.0,
+            0.0,
+            0.0,
+            0.0,
+            1.0,
+            0.0,
+        ])
+    }
+    /// adds `amount` (-1..=1) to the color channels. 0 is unchanged.
+    pub fn brightness(amount: f32) -> Self {
+        Self::translate(amount, amount, amount, 0.0)
+    }
+    /// scales the color channels around 0.5. 0 is gray, 1 is unchanged.
+    pub fn contrast(amount: f32) -> Self {
+        let t = 0.5 * (1.0 - amount);
+        Self([
+            amount, 0.0, 0.0, 0.0, t, //
+            0.0, amount, 0.0, 0.0, t, //
+            0.0, 0.0, amount, 0.0, t, //
+            0.0, 0.0, 0.0, 1.0, 0.0,
+        ])
+    }
+    /// `amount` of 0 is unchanged and 1 is full sepia (same as css's `sepia()`).
+    pub fn sepia(amount: f32) -> Self {
+        let a = amount.clamp(0.0, 1.0);
+        let sepia = Self([
+            0.393, 0.769, 0.189, 0.0, 0.0, //
+            0.349, 0.686, 0.168, 0.0, 0.0, //
+            0.272, 0.534, 0.131, 0.0, 0.0, //
+            0.0, 0.0, 0.0, 1.0, 0.0,
+        ]);
+        Self::IDENTITY.lerp(&sepia, a)
+    }
+    /// `amount` of 0 is unchanged and 1 is fully gray.
+    pub fn grayscale(amount: f32) -> Self {
+        Self::saturation(1.0 - amount.clamp(0.0, 1.0))
+    }
+    /// `amount` of 0 is unchanged and 1 fully inverts the color channels.
+    pub fn invert(amount: f32) -> Self {
+        let s = 1.0 - 2.0 * amount;
+        Self([
+            s, 0.0, 0.0, 0.0, amount, //
+            0.0, s, 0.0, 0.0, amount, //
+            0.0, 0.0, s, 0.0, amount, //
+            0.0, 0.0, 0.0, 1.0, 0.0,
+        ])
+    }
+    /// output channel `i` is the input channel `channels[i]`. eg: `[B, G, R, A]` swaps red and blue.
+    pub fn swizzle(channels: [ColorChannel; 4]) -> Self {
+        let mut m = [0.0; 20];
+        for (row, channel) in channels.into_iter().enumerate() {
+            let column = match channel {
+                sk_color_channel_t::R_SK_COLOR_CHANNEL => 0,
+                sk_color_channel_t::G_SK_COLOR_CHANNEL => 1,
+                sk_color_channel_t::B_SK_COLOR_CHANNEL => 2,
+                sk_color_channel_t::A_SK_COLOR_CHANNEL => 3,
+            };
+            m[row * 5 + column] = 1.0;
+        }
+        Self(m)
+    }
+    /// a matrix that applies `other` first and then `self`.
+    pub fn concat(self, other: Self) -> Self {
+        let (a, b) = (&self.0, &other.0);
+        let mut m = [0.0; 20];
+        for row in 0..4 {
+            for column in 0..5 {
+                let mut v = (0..4)
+                    .map(|k| a[row * 5 + k] * b[k * 5 + column])
+                    .sum::<f32>();
+                if column == 4 {
+                    v += a[row * 5 + 4];
+                }
+                m[row * 5 + column] = v;
+            }
+        }
+        Self(m)
+    }
+    /// applies `other` before `self`
+    pub fn pre_concat(self, other: Self) -> Self {
+        self.concat(other)
+    }
+    /// applies `other` after `self`
+    pub fn post_concat(self, other: Self) -> Self {
+        other.concat(self)
+    }
+    /// interpolates each value. `t` of 0 is `self` and 1 is `other`. Useful to animate adjustments.
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self(std::array::from_fn(|i| {
+            self.0[i] + (other.0[i] - self.0[i]) * t
+        }))
+    }
+    pub fn as_array(&self) -> &[f32; 20] {
+        &self.0
+    }
+}
+impl From<[f32; 20]> for ColorMatrix {
+    fn from(value: [f32; 20]) -> Self {
+        Self(value)
+    }
+}
+impl From<ColorMatrix> for ColorFilter {
+    fn from(value: ColorMatrix) -> Self {
+        ColorFilter::new_color_matrix(&value.0)
+    }
+}
+impl From<&ColorMatrix> for ColorFilter {
+    fn from(value: &ColorMatrix) -> Self {
+        ColorFilter::new_color_matrix(&value.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ColorMatrix;
+
+    fn assert_near(a: ColorMatrix, b: ColorMatrix) {
+        assert!(
+            a.0.iter().zip(b.0).all(|(x, y)| (x - y).abs() < 1e-5),
+            "{a:?} != {b:?}"
+        );
+    }
+
+    /// applies the matrix to an rgba color
+    fn apply(m: ColorMatrix, c: [f32; 4]) -> [f32; 4] {
+        std::array::from_fn(|row| {
+            (0..4).map(|k| m.0[row * 5 + k] * c[k]).sum::<f32>() + m.0[row * 5 + 4]
+        })
+    }
+
+    #[test]
+    pub fn test_identity_concat() {
+        let m = ColorMatrix::sepia(0.7).post_concat(ColorMatrix::brightness(0.2));
+        assert_eq!(ColorMatrix::IDENTITY.concat(m), m);
+        assert_eq!(m.concat(ColorMatrix::IDENTITY), m);
+        assert_eq!(
+            ColorMatrix::IDENTITY.concat(ColorMatrix::IDENTITY),
+            ColorMatrix::IDENTITY
+        );
+        assert_eq!(ColorMatrix::default(), ColorMatrix::IDENTITY);
+    }
+
+    #[test]
+    pub fn test_neutral_adjustments() {
+        assert_eq!(ColorMatrix::saturation(1.0), ColorMatrix::IDENTITY);
+        assert_near(ColorMatrix::hue_rotate(0.0), ColorMatrix::IDENTITY);
+        assert_near(ColorMatrix::hue_rotate(360.0), ColorMatrix::IDENTITY);
+        assert_eq!(ColorMatrix::brightness(0.0), ColorMatrix::IDENTITY);
+        assert_eq!(ColorMatrix::contrast(1.0), ColorMatrix::IDENTITY);
+        assert_eq!(ColorMatrix::invert(0.0), ColorMatrix::IDENTITY);
+        assert_eq!(ColorMatrix::sepia(0.0), ColorMatrix::IDENTITY);
+        assert_eq!(ColorMatrix::grayscale(0.0), ColorMatrix::IDENTITY);
+    }
+
+    #[test]
+    pub fn test_concat_translation() {
+        let scale = ColorMatrix::scale(2.0, 3.0, 4.0, 1.0);
+        let translate = ColorMatrix::translate(0.1, 0.2, 0.3, 0.0);
+        // translate first, then scale: the translation is scaled too
+        let m = scale.concat(translate);
+        assert_near(m, {
+            let mut expected = scale;
+            expected.0[4] = 0.2;
+            expected.0[9] = 0.6;
+            expected.0[14] = 1.2;
+            expected
+        });
+        // scale first, then translate: the translation is kept as is
+        let m = translate.concat(scale);
+        assert_near(m, {
+            let mut expected = scale;
+            expected.0[4] = 0.1;
+            expected.0[9] = 0.2;
+            expected.0[14] = 0.3;
+            expected
+        });
+        // both translations add up
+        assert_near(
+            translate.concat(translate),
+            ColorMatrix::translate(0.2, 0.4, 0.6, 0.0),
+        );
+    }
+
+    #[test]
+    pub fn test_concat_matches_applying_in_order() {
+        let first =
+            ColorMatrix::contrast(1.3).post_concat(ColorMatrix::translate(0.1, 0.0, -0.1, 0.0));
+        let second = ColorMatrix::hue_rotate(40.0);
+        let color = [0.2, 0.5, 0.8, 0.9];
+        let expected = apply(second, apply(first, color));
+        let actual = apply(second.concat(first), color);
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-5, "{actual:?} != {expected:?}");
+        }
+        assert_eq!(second.concat(first), first.post_concat(second));
+        assert_eq!(second.concat(first), second.pre_concat(first));
+    }
+}
@@ -0,0 +1,244 @@
+use std::{
+    borrow::{Borrow, BorrowMut},
+    marker::PhantomData,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::bindings::*;
+use crate::{
+    canvas::Canvas, stream::WStream, string::SkiaString, DocumentPdfMetadata, Rect, SkiaOptPtr,
+    TimeDatetime,
+};
+
+impl TimeDatetime {
+    /// `month` is 1..=12, `day` is 1..=31. `time_zone_minutes` is the offset from UTC.
+    /// The day of week is calculated from the date.
+    pub fn new(
+        year: u16,
+        month: u8,
+        day: u8,
+        hour: u8,
+        minute: u8,
+        second: u8,
+        time_zone_minutes: i16,
+    ) -> Self {
+        Self {
+            fTimeZoneMinutes: time_zone_minutes,
+            fYear: year,
+            fMonth: month,
+            fDayOfWeek: day_of_week(days_from_civil(year as i64, month as i64, day as i64)),
+            fDay: day,
+            fHour: hour,
+            fMinute: minute,
+            fSecond: second,
+        }
+    }
+    /// UTC date and time. Times before the unix epoch are clamped to the epoch.
+    pub fn from_system_time(time: SystemTime) -> Self {
+        let secs = time
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default() as i64;
+        let days = secs.div_euclid(86400);
+        let secs_of_day = secs.rem_euclid(86400);
+        let (year, month, day) = civil_from_days(days);
+        Self {
+            fTimeZoneMinutes: 0,
+            fYear: year.clamp(0, u16::MAX as i64) as u16,
+            fMonth: month as u8,
+            fDayOfWeek: day_of_week(days),
+            fDay: day as u8,
+            fHour: (secs_of_day / 3600) as u8,
+            fMinute: (secs_of_day % 3600 / 60) as u8,
+            fSecond: (secs_of_day % 60) as u8,
+        }
+    }
+    pub fn now() -> Self {
+        Self::from_system_time(SystemTime::now())
+    }
+}
+/// days since 1970-01-01 for a proleptic gregorian date.
+/// <http://howardhinnant.github.io/date_algorithms.html#days_from_civil>
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+/// inverse of [days_from_civil]. returns (year, month, day)
+fn civil_from_days(z: i64) -> (i64, i64, i64) {
+    let z = z + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    (yoe + era * 400 + i64::from(m <= 2), m, d)
+}
+/// 0 is sunday
+fn day_of_week(days_since_epoch: i64) -> u8 {
+    // 1970-01-01 was a thursday
+    (days_since_epoch + 4).rem_euclid(7) as u8
+}
+
+/// Metadata written into the pdf's document information dictionary.
+/// Strings and dates which are None are omitted.
+#[derive(Debug, Clone)]
+pub struct PdfMetadata {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    /// comma separated
+    pub keywords: Option<String>,
+    /// the program that created the original content. eg: your app's name
+    pub creator: Option<String>,
+    /// the program that produced the pdf. If None, skia uses "Skia/PDF m<version>"
+    pub producer: Option<String>,
+    pub creation: Option<TimeDatetime>,
+    pub modified: Option<TimeDatetime>,
+    /// dpi used when some content (eg: some image filters) needs to be rasterized. 72 by default.
+    pub raster_dpi: f32,
+    /// produce a PDF/A-2b conforming document. Makes the files bigger.
+    pub pdf_a: bool,
+    /// jpeg quality for images that are not already encoded. 101 (default) means lossless encoding.
+    pub encoding_quality: i32,
+}
+impl Default for PdfMetadata {
+    fn default() -> Self {
+        Self {
+            title: None,
+            author: None,
+            subject: None,
+            keywords: None,
+            creator: None,
+            producer: None,
+            creation: None,
+            modified: None,
+            raster_dpi: 72.0,
+            pdf_a: false,
+            encoding_quality: 101,
+        }
+    }
+}
+
+/// A multi-page pdf that is written into a [WStream] as pages are finished.
+///
+/// Dropping the document closes it (writing the remaining pdf structures into the stream).
+/// Use [Self::abort] if you want to stop without producing a valid pdf.
+pub struct PdfDocument<'s> {
+    inner: *mut sk_document_t,
+    phantom: PhantomData<&'s mut ()>,
+}
+impl Drop for PdfDocument<'_> {
+    fn drop(&mut self) {
+        // unref closes the document if it is not closed (or aborted) yet
+        unsafe { sk_document_unref(self.inner) }
+    }
+}
+impl<'s> PdfDocument<'s> {
+    /// None if skia was built without pdf support.
+    pub fn new(stream: &'s mut impl WStream) -> Option<Self> {
+        let inner = unsafe { sk_document_create_pdf_from_stream(stream.borrow_wstream_mut_ptr()) };
+        (!inner.is_null()).then_some(Self {
+            inner,
+            phantom: PhantomData,
+        })
+    }
+    /// None if skia was built without pdf support.
+    pub fn new_with_metadata(stream: &'s mut impl WStream, metadata: &PdfMetadata) -> Option<Self> {
+        // skia copies the strings and dates, so they only need to live until the document is created.
+        let string = |s: &Option<String>| s.as_deref().map(SkiaString::new_with_copy);
+        let title = string(&metadata.title);
+        let author = string(&metadata.author);
+        let subject = string(&metadata.subject);
+        let keywords = string(&metadata.keywords);
+        let creator = string(&metadata.creator);
+        let producer = string(&metadata.producer);
+        let mut creation = metadata.creation;
+        let mut modified = metadata.modified;
+        let native = DocumentPdfMetadata {
+            fTitle: title.as_ref().or_null() as _,
+            fAuthor: author.as_ref().or_null() as _,
+            fSubject: subject.as_ref().or_null() as _,
+            fKeywords: keywords.as_ref().or_null() as _,
+            fCreator: creator.as_ref().or_null() as _,
+            fProducer: producer.as_ref().or_null() as _,
+            fCreation: creation
+                .as_mut()
+                .map_or(std::ptr::null_mut(), |c| c.as_ptr_mut()),
+            fModified: modified
+                .as_mut()
+                .map_or(std::ptr::null_mut(), |m| m.as_ptr_mut()),
+            fRasterDPI: metadata.raster_dpi,
+            fPDFA: metadata.pdf_a,
+            fEncodingQuality: metadata.encoding_quality,
+        };
+        let inner = unsafe {
+            sk_document_create_pdf_from_stream_with_metadata(
+                stream.borrow_wstream_mut_ptr(),
+                native.as_ptr(),
+            )
+        };
+        (!inner.is_null()).then_some(Self {
+            inner,
+            phantom: PhantomData,
+        })
+    }
+    /// starts a new page of `width` x `height` points (1/72 of an inch).
+    /// `content` restricts drawing to that part of the page. The canvas is translated so that
+    /// the top left of `content` is at the origin.
+    ///
+    /// The page ends when the returned [PdfPage] is dropped (or [PdfPage::end] is called).
+    pub fn begin_page(&mut self, width: f32, height: f32, content: Option<&Rect>) -> PdfPage<'_> {
+        let canvas =
+            unsafe { sk_document_begin_page(self.inner, width, height, content.or_null()) };
+        assert!(!canvas.is_null());
+        PdfPage {
+            canvas,
+            document: self.inner,
+            phantom: PhantomData,
+        }
+    }
+    /// writes the remaining pdf structures and flushes the stream. Same as dropping the document.
+    pub fn close(self) {
+        unsafe { sk_document_close(self.inner) }
+    }
+    /// stops producing the pdf. The contents of the stream are undefined (probably an incomplete pdf).
+    pub fn abort(self) {
+        unsafe { sk_document_abort(self.inner) }
+    }
+}
+/// The canvas of a page that is currently being drawn. Ends the page when dropped.
+pub struct PdfPage<'d> {
+    canvas: *mut sk_canvas_t,
+    document: *mut sk_document_t,
+    phantom: PhantomData<&'d mut ()>,
+}
+impl Drop for PdfPage<'_> {
+    fn drop(&mut self) {
+        unsafe { sk_document_end_page(self.document) }
+    }
+}
+impl PdfPage<'_> {
+    /// same as dropping the page
+    pub fn end(self) {}
+}
+impl AsMut<Canvas> for PdfPage<'_> {
+    fn as_mut(&mut self) -> &mut Canvas {
+        unsafe { std::mem::transmute(&mut self.canvas) }
+    }
+}
+impl Borrow<Canvas> for PdfPage<'_> {
+    fn borrow(&self) -> &Canvas {
+        unsafe { std::mem::transmute(&self.canvas) }
+    }
+}
+impl BorrowMut<Canvas> for PdfPage<'_> {
+    fn borrow_mut(&mut self) -> &mut Canvas {
+        unsafe { std::mem::transmute(&mut self.canvas) }
+    }
+}
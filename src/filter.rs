@@ -1,7 +1,8 @@
 use crate::{bindings::*, ColorChannel};
 
 use crate::{
-    color::Color, shader::Shader, skia_wrapper, BlendMode, BlurStyle, Highcontrastconfig, Rect,
+    color::Color, image::Image, picture::Picture, shader::Shader, skia_wrapper, BlendMode,
+    BlurStyle, Highcontrastconfig, IPoint, ISize, Matrix, Point3, Rect, SamplingOptions,
     ShaderTileMode, SkiaOptPtr,
 };
 
@@ -53,8 +54,8 @@ impl ColorFilter {
     pub fn new_compose(outer: &Self, inner: &Self) -> Self {
         unsafe { Self::from_owned_ptr(sk_colorfilter_new_compose(outer.inner, inner.inner)) }
     }
-    pub fn new_color_matrix(array: &[f32]) -> Self {
-        assert!(array.len() >= 20);
+    /// a 4x5 row-major matrix. See [crate::color_matrix::ColorMatrix] for common adjustments.
+    pub fn new_color_matrix(array: &[f32; 20]) -> Self {
         unsafe { Self::from_owned_ptr(sk_colorfilter_new_color_matrix(array.as_ptr())) }
     }
     pub fn new_luma_color() -> Self {
@@ -217,140 +218,347 @@ impl ImageFilter {
             ))
         }
     }
-    /*
-    pub fn sk_imagefilter_new_image(
-        image: *mut sk_image_t,
-        srcRect: *const sk_rect_t,
-        dstRect: *const sk_rect_t,
-        sampling: *const sk_sampling_options_t,
-    ) -> *mut sk_imagefilter_t;
-    pub fn sk_imagefilter_new_image_simple(
-        image: *mut sk_image_t,
-        sampling: *const sk_sampling_options_t,
-    ) -> *mut sk_imagefilter_t;
-    pub fn sk_imagefilter_new_magnifier(
-        lensBounds: *const sk_rect_t,
-        zoomAmount: f32,
+    /// draws `src_rect` of `image` into `dst_rect`. The filter ignores its source.
+    pub fn new_image(
+        image: &Image,
+        src_rect: &Rect,
+        dst_rect: &Rect,
+        sampling: &SamplingOptions,
+    ) -> Self {
+        unsafe {
+            Self::from_owned_ptr(sk_imagefilter_new_image(
+                image.inner,
+                src_rect.as_ptr(),
+                dst_rect.as_ptr(),
+                sampling.as_ptr(),
+            ))
+        }
+    }
+    /// draws the whole image at its original size.
+    pub fn new_image_simple(image: &Image, sampling: &SamplingOptions) -> Self {
+        unsafe {
+            Self::from_owned_ptr(sk_imagefilter_new_image_simple(
+                image.inner,
+                sampling.as_ptr(),
+            ))
+        }
+    }
+    /// magnifies the content inside `lens_bounds` by `zoom_amount` (> 0), blending to unmagnified
+    /// content over `inset` pixels at the edges.
+    /// None if `zoom_amount` is not positive, `inset` is negative or `lens_bounds` is not finite.
+    pub fn new_magnifier(
+        lens_bounds: &Rect,
+        zoom_amount: f32,
         inset: f32,
-        sampling: *const sk_sampling_options_t,
-        input: *const sk_imagefilter_t,
-        cropRect: *const sk_rect_t,
-    ) -> *mut sk_imagefilter_t;
-    pub fn sk_imagefilter_new_matrix_convolution(
-        kernelSize: *const sk_isize_t,
-        kernel: *const f32,
+        sampling: &SamplingOptions,
+        input: Option<&Self>,
+        crop_rect: Option<&Rect>,
+    ) -> Option<Self> {
+        unsafe {
+            Self::try_from_owned_ptr(sk_imagefilter_new_magnifier(
+                lens_bounds.as_ptr(),
+                zoom_amount,
+                inset,
+                sampling.as_ptr(),
+                input.or_null(),
+                crop_rect.or_null(),
+            ))
+        }
+    }
+    /// convolves the input with `kernel`, a row-major matrix of `kernel_size.w` x `kernel_size.h` values.
+    /// The result is `sum * gain + bias`. `kernel_offset` is the kernel's target pixel.
+    /// None if the kernel is empty, too big or `kernel_offset` is outside of it.
+    ///
+    /// panics if the length of `kernel` doesn't match `kernel_size`
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_matrix_convolution(
+        kernel_size: &ISize,
+        kernel: &[f32],
         gain: f32,
         bias: f32,
-        kernelOffset: *const sk_ipoint_t,
-        ctileMode: sk_shader_tilemode_t,
-        convolveAlpha: bool,
-        input: *const sk_imagefilter_t,
-        cropRect: *const sk_rect_t,
-    ) -> *mut sk_imagefilter_t;
-    pub fn sk_imagefilter_new_matrix_transform(
-        cmatrix: *const sk_matrix_t,
-        sampling: *const sk_sampling_options_t,
-        input: *const sk_imagefilter_t,
-    ) -> *mut sk_imagefilter_t;
-    pub fn sk_imagefilter_new_merge(
-        cfilters: *mut *const sk_imagefilter_t,
-        count: ::std::os::raw::c_int,
-        cropRect: *const sk_rect_t,
-    ) -> *mut sk_imagefilter_t;
-    pub fn sk_imagefilter_new_merge_simple(
-        first: *const sk_imagefilter_t,
-        second: *const sk_imagefilter_t,
-        cropRect: *const sk_rect_t,
-    ) -> *mut sk_imagefilter_t;
-    pub fn sk_imagefilter_new_offset(
+        kernel_offset: &IPoint,
+        tile_mode: ShaderTileMode,
+        convolve_alpha: bool,
+        input: Option<&Self>,
+        crop_rect: Option<&Rect>,
+    ) -> Option<Self> {
+        assert_eq!(
+            kernel.len() as i64,
+            kernel_size.w.max(0) as i64 * kernel_size.h.max(0) as i64
+        );
+        unsafe {
+            Self::try_from_owned_ptr(sk_imagefilter_new_matrix_convolution(
+                kernel_size.as_ptr(),
+                kernel.as_ptr(),
+                gain,
+                bias,
+                kernel_offset.as_ptr(),
+                tile_mode,
+                convolve_alpha,
+                input.or_null(),
+                crop_rect.or_null(),
+            ))
+        }
+    }
+    /// None if the matrix is not finite.
+    pub fn new_matrix_transform(
+        matrix: &Matrix,
+        sampling: &SamplingOptions,
+        input: Option<&Self>,
+    ) -> Option<Self> {
+        unsafe {
+            Self::try_from_owned_ptr(sk_imagefilter_new_matrix_transform(
+                matrix.as_ptr(),
+                sampling.as_ptr(),
+                input.or_null(),
+            ))
+        }
+    }
+    /// draws the filters on top of each other with src-over, first to last. None uses the source.
+    pub fn new_merge(filters: &[Option<&Self>], crop_rect: Option<&Rect>) -> Self {
+        let mut filters: Vec<*const sk_imagefilter_t> =
+            filters.iter().map(|f| f.or_null()).collect();
+        unsafe {
+            Self::from_owned_ptr(sk_imagefilter_new_merge(
+                filters.as_mut_ptr(),
+                filters.len() as _,
+                crop_rect.or_null(),
+            ))
+        }
+    }
+    pub fn new_merge_simple(
+        first: Option<&Self>,
+        second: Option<&Self>,
+        crop_rect: Option<&Rect>,
+    ) -> Self {
+        unsafe {
+            Self::from_owned_ptr(sk_imagefilter_new_merge_simple(
+                first.or_null(),
+                second.or_null(),
+                crop_rect.or_null(),
+            ))
+        }
+    }
+    /// None if the offset is not finite.
+    pub fn new_offset(
         dx: f32,
         dy: f32,
-        input: *const sk_imagefilter_t,
-        cropRect: *const sk_rect_t,
-    ) -> *mut sk_imagefilter_t;
-    pub fn sk_imagefilter_new_picture(picture: *const sk_picture_t) -> *mut sk_imagefilter_t;
-    pub fn sk_imagefilter_new_picture_with_rect(
-        picture: *const sk_picture_t,
-        targetRect: *const sk_rect_t,
-    ) -> *mut sk_imagefilter_t;
-    pub fn sk_imagefilter_new_shader(
-        shader: *const sk_shader_t,
-        dither: bool,
-        cropRect: *const sk_rect_t,
-    ) -> *mut sk_imagefilter_t;
-    pub fn sk_imagefilter_new_tile(
-        src: *const sk_rect_t,
-        dst: *const sk_rect_t,
-        input: *const sk_imagefilter_t,
-    ) -> *mut sk_imagefilter_t;
-    pub fn sk_imagefilter_new_dilate(
-        radiusX: f32,
-        radiusY: f32,
-        input: *const sk_imagefilter_t,
-        cropRect: *const sk_rect_t,
-    ) -> *mut sk_imagefilter_t;
-    pub fn sk_imagefilter_new_erode(
-        radiusX: f32,
-        radiusY: f32,
-        input: *const sk_imagefilter_t,
-        cropRect: *const sk_rect_t,
-    ) -> *mut sk_imagefilter_t;
-    pub fn sk_imagefilter_new_distant_lit_diffuse(
-        direction: *const sk_point3_t,
-        lightColor: sk_color_t,
-        surfaceScale: f32,
+        input: Option<&Self>,
+        crop_rect: Option<&Rect>,
+    ) -> Option<Self> {
+        unsafe {
+            Self::try_from_owned_ptr(sk_imagefilter_new_offset(
+                dx,
+                dy,
+                input.or_null(),
+                crop_rect.or_null(),
+            ))
+        }
+    }
+    /// draws the picture. The filter ignores its source.
+    pub fn new_picture(picture: &Picture) -> Self {
+        unsafe { Self::from_owned_ptr(sk_imagefilter_new_picture(picture.as_ptr())) }
+    }
+    /// same as [Self::new_picture], but only the part of the picture inside `target_rect` is drawn.
+    pub fn new_picture_with_rect(picture: &Picture, target_rect: &Rect) -> Self {
+        unsafe {
+            Self::from_owned_ptr(sk_imagefilter_new_picture_with_rect(
+                picture.as_ptr(),
+                target_rect.as_ptr(),
+            ))
+        }
+    }
+    /// fills the output with the shader. The filter ignores its source.
+    pub fn new_shader(shader: &Shader, dither: bool, crop_rect: Option<&Rect>) -> Self {
+        unsafe {
+            Self::from_owned_ptr(sk_imagefilter_new_shader(
+                shader.as_ptr(),
+                dither,
+                crop_rect.or_null(),
+            ))
+        }
+    }
+    /// repeats the `src` part of the input to fill `dst`. None if the rects aren't finite.
+    pub fn new_tile(src: &Rect, dst: &Rect, input: Option<&Self>) -> Option<Self> {
+        unsafe {
+            Self::try_from_owned_ptr(sk_imagefilter_new_tile(
+                src.as_ptr(),
+                dst.as_ptr(),
+                input.or_null(),
+            ))
+        }
+    }
+    /// grows the opaque parts of the input. None if a radius is negative.
+    pub fn new_dilate(
+        radius_x: f32,
+        radius_y: f32,
+        input: Option<&Self>,
+        crop_rect: Option<&Rect>,
+    ) -> Option<Self> {
+        unsafe {
+            Self::try_from_owned_ptr(sk_imagefilter_new_dilate(
+                radius_x,
+                radius_y,
+                input.or_null(),
+                crop_rect.or_null(),
+            ))
+        }
+    }
+    /// shrinks the opaque parts of the input. None if a radius is negative.
+    pub fn new_erode(
+        radius_x: f32,
+        radius_y: f32,
+        input: Option<&Self>,
+        crop_rect: Option<&Rect>,
+    ) -> Option<Self> {
+        unsafe {
+            Self::try_from_owned_ptr(sk_imagefilter_new_erode(
+                radius_x,
+                radius_y,
+                input.or_null(),
+                crop_rect.or_null(),
+            ))
+        }
+    }
+}
+/// Lighting filters use the alpha channel of their input as a height map (scaled by `surface_scale`)
+/// and light it with a distant, point or spot light. `kd` is the diffuse and `ks` the specular reflectance.
+/// They return None if a parameter is not finite.
+impl ImageFilter {
+    /// `direction` points from the surface towards the light.
+    pub fn new_distant_lit_diffuse(
+        direction: &Point3,
+        light_color: Color,
+        surface_scale: f32,
         kd: f32,
-        input: *const sk_imagefilter_t,
-        cropRect: *const sk_rect_t,
-    ) -> *mut sk_imagefilter_t;
-    pub fn sk_imagefilter_new_point_lit_diffuse(
-        location: *const sk_point3_t,
-        lightColor: sk_color_t,
-        surfaceScale: f32,
+        input: Option<&Self>,
+        crop_rect: Option<&Rect>,
+    ) -> Option<Self> {
+        unsafe {
+            Self::try_from_owned_ptr(sk_imagefilter_new_distant_lit_diffuse(
+                direction.as_ptr(),
+                light_color.as_u32(),
+                surface_scale,
+                kd,
+                input.or_null(),
+                crop_rect.or_null(),
+            ))
+        }
+    }
+    pub fn new_point_lit_diffuse(
+        location: &Point3,
+        light_color: Color,
+        surface_scale: f32,
         kd: f32,
-        input: *const sk_imagefilter_t,
-        cropRect: *const sk_rect_t,
-    ) -> *mut sk_imagefilter_t;
-    pub fn sk_imagefilter_new_spot_lit_diffuse(
-        location: *const sk_point3_t,
-        target: *const sk_point3_t,
-        specularExponent: f32,
-        cutoffAngle: f32,
-        lightColor: sk_color_t,
-        surfaceScale: f32,
+        input: Option<&Self>,
+        crop_rect: Option<&Rect>,
+    ) -> Option<Self> {
+        unsafe {
+            Self::try_from_owned_ptr(sk_imagefilter_new_point_lit_diffuse(
+                location.as_ptr(),
+                light_color.as_u32(),
+                surface_scale,
+                kd,
+                input.or_null(),
+                crop_rect.or_null(),
+            ))
+        }
+    }
+    /// a light at `location` pointing at `target`. `cutoff_angle` is in degrees.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_spot_lit_diffuse(
+        location: &Point3,
+        target: &Point3,
+        specular_exponent: f32,
+        cutoff_angle: f32,
+        light_color: Color,
+        surface_scale: f32,
         kd: f32,
-        input: *const sk_imagefilter_t,
-        cropRect: *const sk_rect_t,
-    ) -> *mut sk_imagefilter_t;
-    pub fn sk_imagefilter_new_distant_lit_specular(
-        direction: *const sk_point3_t,
-        lightColor: sk_color_t,
-        surfaceScale: f32,
+        input: Option<&Self>,
+        crop_rect: Option<&Rect>,
+    ) -> Option<Self> {
+        unsafe {
+            Self::try_from_owned_ptr(sk_imagefilter_new_spot_lit_diffuse(
+                location.as_ptr(),
+                target.as_ptr(),
+                specular_exponent,
+                cutoff_angle,
+                light_color.as_u32(),
+                surface_scale,
+                kd,
+                input.or_null(),
+                crop_rect.or_null(),
+            ))
+        }
+    }
+    pub fn new_distant_lit_specular(
+        direction: &Point3,
+        light_color: Color,
+        surface_scale: f32,
         ks: f32,
         shininess: f32,
-        input: *const sk_imagefilter_t,
-        cropRect: *const sk_rect_t,
-    ) -> *mut sk_imagefilter_t;
-    pub fn sk_imagefilter_new_point_lit_specular(
-        location: *const sk_point3_t,
-        lightColor: sk_color_t,
-        surfaceScale: f32,
+        input: Option<&Self>,
+        crop_rect: Option<&Rect>,
+    ) -> Option<Self> {
+        unsafe {
+            Self::try_from_owned_ptr(sk_imagefilter_new_distant_lit_specular(
+                direction.as_ptr(),
+                light_color.as_u32(),
+                surface_scale,
+                ks,
+                shininess,
+                input.or_null(),
+                crop_rect.or_null(),
+            ))
+        }
+    }
+    pub fn new_point_lit_specular(
+        location: &Point3,
+        light_color: Color,
+        surface_scale: f32,
         ks: f32,
         shininess: f32,
-        input: *const sk_imagefilter_t,
-        cropRect: *const sk_rect_t,
-    ) -> *mut sk_imagefilter_t;
-    pub fn sk_imagefilter_new_spot_lit_specular(
-        location: *const sk_point3_t,
-        target: *const sk_point3_t,
-        specularExponent: f32,
-        cutoffAngle: f32,
-        lightColor: sk_color_t,
-        surfaceScale: f32,
+        input: Option<&Self>,
+        crop_rect: Option<&Rect>,
+    ) -> Option<Self> {
+        unsafe {
+            Self::try_from_owned_ptr(sk_imagefilter_new_point_lit_specular(
+                location.as_ptr(),
+                light_color.as_u32(),
+                surface_scale,
+                ks,
+                shininess,
+                input.or_null(),
+                crop_rect.or_null(),
+            ))
+        }
+    }
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_spot_lit_specular(
+        location: &Point3,
+        target: &Point3,
+        specular_exponent: f32,
+        cutoff_angle: f32,
+        light_color: Color,
+        surface_scale: f32,
         ks: f32,
         shininess: f32,
-        input: *const sk_imagefilter_t,
-        cropRect: *const sk_rect_t,
-    ) -> *mut sk_imagefilter_t;
-     */
+        input: Option<&Self>,
+        crop_rect: Option<&Rect>,
+    ) -> Option<Self> {
+        unsafe {
+            Self::try_from_owned_ptr(sk_imagefilter_new_spot_lit_specular(
+                location.as_ptr(),
+                target.as_ptr(),
+                specular_exponent,
+                cutoff_angle,
+                light_color.as_u32(),
+                surface_scale,
+                ks,
+                shininess,
+                input.or_null(),
+                crop_rect.or_null(),
+            ))
+        }
+    }
 }
@@ -0,0 +1,1038 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    str::FromStr,
+};
+
+use crate::{
+    color::Color,
+    filter::{ColorFilter, ImageFilter},
+    BlendMode, Point3, Rect, ShaderTileMode,
+};
+
+/// keeps the alpha channel and sets the color channels to black, like svg's `SourceAlpha`.
+const SOURCE_ALPHA_MATRIX: [f32; 20] = [
+    0.0, 0.0, 0.0, 0.0, 0.0, //
+    0.0, 0.0, 0.0, 0.0, 0.0, //
+    0.0, 0.0, 0.0, 0.0, 0.0, //
+    0.0, 0.0, 0.0, 1.0, 0.0,
+];
+
+/// Where a node gets an input image from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FilterInput {
+    /// the content being filtered
+    SourceGraphic,
+    /// the alpha channel of the content being filtered, with black color channels
+    SourceAlpha,
+    /// the result of the node with this name
+    Node(String),
+}
+impl From<&str> for FilterInput {
+    /// `SourceGraphic` and `SourceAlpha` are the sources. Anything else is a node name.
+    fn from(value: &str) -> Self {
+        match value {
+            "SourceGraphic" => Self::SourceGraphic,
+            "SourceAlpha" => Self::SourceAlpha,
+            name => Self::Node(name.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Light {
+    /// infinitely far away. `direction` points from the surface towards the light.
+    Distant {
+        direction: Point3,
+    },
+    Point {
+        location: Point3,
+    },
+    /// `cutoff_angle` is in degrees
+    Spot {
+        location: Point3,
+        target: Point3,
+        specular_exponent: f32,
+        cutoff_angle: f32,
+    },
+}
+
+/// The operation of a node and the edges to its inputs.
+#[derive(Debug, Clone)]
+pub enum FilterOp {
+    Blur {
+        sigma_x: f32,
+        sigma_y: f32,
+        input: FilterInput,
+    },
+    Offset {
+        dx: f32,
+        dy: f32,
+        input: FilterInput,
+    },
+    /// see [ColorFilter::new_color_matrix]
+    ColorMatrix {
+        matrix: [f32; 20],
+        input: FilterInput,
+    },
+    /// draws `foreground` over `background` with `mode`
+    Blend {
+        mode: BlendMode,
+        background: FilterInput,
+        foreground: FilterInput,
+    },
+    /// draws the inputs on top of each other, first to last
+    Merge { inputs: Vec<FilterInput> },
+    Dilate {
+        radius_x: f32,
+        radius_y: f32,
+        input: FilterInput,
+    },
+    Erode {
+        radius_x: f32,
+        radius_y: f32,
+        input: FilterInput,
+    },
+    DiffuseLighting {
+        light: Light,
+        color: Color,
+        surface_scale: f32,
+        kd: f32,
+        input: FilterInput,
+    },
+    SpecularLighting {
+        light: Light,
+        color: Color,
+        surface_scale: f32,
+        ks: f32,
+        shininess: f32,
+        input: FilterInput,
+    },
+}
+impl FilterOp {
+    /// false if a number is not finite, or a blur sigma or morphology radius is negative.
+    /// skia rejects those, so they are checked before building.
+    pub fn has_valid_parameters(&self) -> bool {
+        let light_numbers = |light: &Light| match light {
+            Light::Distant { direction } => vec![direction.x, direction.y, direction.z],
+            Light::Point { location } => vec![location.x, location.y, location.z],
+            Light::Spot {
+                location,
+                target,
+                specular_exponent,
+                cutoff_angle,
+            } => vec![
+                location.x,
+                location.y,
+                location.z,
+                target.x,
+                target.y,
+                target.z,
+                *specular_exponent,
+                *cutoff_angle,
+            ],
+        };
+        let (numbers, non_negative) = match self {
+            FilterOp::Blur {
+                sigma_x, sigma_y, ..
+            } => (vec![*sigma_x, *sigma_y], true),
+            FilterOp::Offset { dx, dy, .. } => (vec![*dx, *dy], false),
+            FilterOp::ColorMatrix { matrix, .. } => (matrix.to_vec(), false),
+            FilterOp::Blend { .. } | FilterOp::Merge { .. } => (vec![], false),
+            FilterOp::Dilate {
+                radius_x, radius_y, ..
+            }
+            | FilterOp::Erode {
+                radius_x, radius_y, ..
+            } => (vec![*radius_x, *radius_y], true),
+            FilterOp::DiffuseLighting {
+                light,
+                surface_scale,
+                kd,
+                ..
+            } => {
+                let mut numbers = light_numbers(light);
+                numbers.extend([*surface_scale, *kd]);
+                (numbers, false)
+            }
+            FilterOp::SpecularLighting {
+                light,
+                surface_scale,
+                ks,
+                shininess,
+                ..
+            } => {
+                let mut numbers = light_numbers(light);
+                numbers.extend([*surface_scale, *ks, *shininess]);
+                (numbers, false)
+            }
+        };
+        numbers
+            .iter()
+            .all(|n| n.is_finite() && (!non_negative || *n >= 0.0))
+    }
+    pub fn inputs(&self) -> Vec<&FilterInput> {
+        match self {
+            FilterOp::Blur { input, .. }
+            | FilterOp::Offset { input, .. }
+            | FilterOp::ColorMatrix { input, .. }
+            | FilterOp::Dilate { input, .. }
+            | FilterOp::Erode { input, .. }
+            | FilterOp::DiffuseLighting { input, .. }
+            | FilterOp::SpecularLighting { input, .. } => vec![input],
+            FilterOp::Blend {
+                background,
+                foreground,
+                ..
+            } => vec![background, foreground],
+            FilterOp::Merge { inputs } => inputs.iter().collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FilterNode {
+    pub name: String,
+    pub op: FilterOp,
+    pub crop_rect: Option<Rect>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterGraphError {
+    /// the graph has no nodes
+    Empty,
+    DuplicateNode(String),
+    /// node names must be made of ascii letters, digits, `_` or `-`,
+    /// and must not be `SourceGraphic`, `SourceAlpha` or `output`
+    InvalidName(String),
+    /// `node` uses `input`, but there is no node with that name
+    MissingInput {
+        node: String,
+        input: String,
+    },
+    /// the output was set to a node that doesn't exist
+    UnknownOutput(String),
+    /// the node (indirectly) uses itself as an input
+    Cycle(String),
+    /// the parameters of the node are out of range (eg: a negative radius or a NaN)
+    InvalidParameters(String),
+    /// a line of the text format could not be parsed. Lines start at 1.
+    Parse {
+        line: usize,
+        message: String,
+    },
+}
+impl Display for FilterGraphError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FilterGraphError::Empty => write!(f, "filter graph has no nodes"),
+            FilterGraphError::DuplicateNode(name) => write!(f, "duplicate node {name}"),
+            FilterGraphError::InvalidName(name) => write!(f, "invalid node name {name:?}"),
+            FilterGraphError::MissingInput { node, input } => {
+                write!(f, "node {node} uses missing input {input}")
+            }
+            FilterGraphError::UnknownOutput(name) => write!(f, "output node {name} does not exist"),
+            FilterGraphError::Cycle(name) => write!(f, "node {name} is part of a cycle"),
+            FilterGraphError::InvalidParameters(name) => {
+                write!(f, "node {name} has invalid parameters")
+            }
+            FilterGraphError::Parse { line, message } => write!(f, "line {line}: {message}"),
+        }
+    }
+}
+impl std::error::Error for FilterGraphError {}
+
+/// A graph of named filter nodes, similar to the primitives of an svg `<filter>`, compiled into a single [ImageFilter].
+///
+/// The output is the last added node, unless set with [Self::set_output].
+///
+/// ```rust,ignore
+/// let graph: FilterGraph = "
+///     shadow = blur sigma=4 in=SourceAlpha
+///     moved = offset d=3,3
+///     result = merge in=moved,SourceGraphic
+/// ".parse()?;
+/// paint.set_image_filter(Some(&mut graph.compile()?));
+/// ```
+///
+/// ## Text format
+///
+/// One node per line: `<name> = <op> <key>=<value>...`. Lists are comma separated.
+/// Empty lines and lines starting with `#` are ignored. `output <name>` sets the output node.
+/// `in` defaults to the previous node (or `SourceGraphic` for the first node). Every op accepts `crop=<l>,<t>,<r>,<b>`.
+///
+/// - `blur sigma=<x>[,<y>] in=`
+/// - `offset d=<dx>,<dy> in=`
+/// - `color_matrix values=<20 numbers> in=`
+/// - `blend mode=<mode> in=<foreground> in2=<background>`. modes are skia's names in lower case, eg: `srcover`, `multiply`.
+/// - `merge in=<a>,<b>...`
+/// - `dilate radius=<x>[,<y>] in=` and `erode radius=<x>[,<y>] in=`
+/// - `diffuse <light> color=<#rrggbb or #aarrggbb> surface_scale= kd= in=`
+/// - `specular <light> color= surface_scale= ks= shininess= in=`
+///
+/// where `<light>` is one of `distant=<x>,<y>,<z>` (the direction), `point=<x>,<y>,<z>` (the location),
+/// or `spot=<x>,<y>,<z> target=<x>,<y>,<z> exponent= cutoff=`.
+#[derive(Debug, Clone, Default)]
+pub struct FilterGraph {
+    nodes: Vec<FilterNode>,
+    output: Option<String>,
+}
+impl FilterGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// names must be unique, made of ascii letters, digits, `_` or `-`,
+    /// and must not be `SourceGraphic`, `SourceAlpha` or `output`.
+    pub fn add_node(
+        &mut self,
+        name: impl Into<String>,
+        op: FilterOp,
+        crop_rect: Option<Rect>,
+    ) -> Result<&mut Self, FilterGraphError> {
+        let name = name.into();
+        if !is_valid_name(&name) {
+            return Err(FilterGraphError::InvalidName(name));
+        }
+        if self.get_node(&name).is_some() {
+            return Err(FilterGraphError::DuplicateNode(name));
+        }
+        self.nodes.push(FilterNode {
+            name,
+            op,
+            crop_rect,
+        });
+        Ok(self)
+    }
+    pub fn set_output(&mut self, name: impl Into<String>) -> &mut Self {
+        self.output = Some(name.into());
+        self
+    }
+    pub fn get_nodes(&self) -> &[FilterNode] {
+        &self.nodes
+    }
+    pub fn get_node(&self, name: &str) -> Option<&FilterNode> {
+        self.nodes.iter().find(|n| n.name == name)
+    }
+    /// the name of the output node
+    pub fn get_output(&self) -> Option<&str> {
+        self.output
+            .as_deref()
+            .or_else(|| self.nodes.last().map(|n| n.name.as_str()))
+    }
+    /// checks that all inputs exist, that there are no cycles and that the parameters are in range.
+    /// Nodes that don't contribute to the output are checked too.
+    pub fn validate(&self) -> Result<(), FilterGraphError> {
+        let output = self.get_output().ok_or(FilterGraphError::Empty)?;
+        if self.get_node(output).is_none() {
+            return Err(FilterGraphError::UnknownOutput(output.to_string()));
+        }
+        let nodes = self.node_map();
+        for node in &self.nodes {
+            if !node.op.has_valid_parameters()
+                || node.crop_rect.is_some_and(|r| {
+                    ![r.left, r.top, r.right, r.bottom]
+                        .iter()
+                        .all(|n| n.is_finite())
+                })
+            {
+                return Err(FilterGraphError::InvalidParameters(node.name.clone()));
+            }
+            for input in node.op.inputs() {
+                if let FilterInput::Node(name) = input {
+                    if !nodes.contains_key(name.as_str()) {
+                        return Err(FilterGraphError::MissingInput {
+                            node: node.name.clone(),
+                            input: name.clone(),
+                        });
+                    }
+                }
+            }
+        }
+        let mut done = HashSet::new();
+        for node in &self.nodes {
+            self.check_cycles(&nodes, node, &mut HashSet::new(), &mut done)?;
+        }
+        Ok(())
+    }
+    /// validates the graph and builds the filter of the output node.
+    /// Nodes used by multiple other nodes are only built once.
+    pub fn compile(&self) -> Result<ImageFilter, FilterGraphError> {
+        self.validate()?;
+        let nodes = self.node_map();
+        let mut built = HashMap::new();
+        let output = self.get_output().ok_or(FilterGraphError::Empty)?;
+        self.build(&nodes, &FilterInput::from(output), &mut built)?;
+        built
+            .remove(output)
+            .ok_or_else(|| FilterGraphError::UnknownOutput(output.to_string()))
+    }
+    fn node_map(&self) -> HashMap<&str, &FilterNode> {
+        self.nodes.iter().map(|n| (n.name.as_str(), n)).collect()
+    }
+    fn check_cycles<'a>(
+        &'a self,
+        nodes: &HashMap<&str, &'a FilterNode>,
+        node: &'a FilterNode,
+        visiting: &mut HashSet<&'a str>,
+        done: &mut HashSet<&'a str>,
+    ) -> Result<(), FilterGraphError> {
+        if done.contains(node.name.as_str()) {
+            return Ok(());
+        }
+        if !visiting.insert(node.name.as_str()) {
+            return Err(FilterGraphError::Cycle(node.name.clone()));
+        }
+        for input in node.op.inputs() {
+            if let FilterInput::Node(name) = input {
+                if let Some(&input) = nodes.get(name.as_str()) {
+                    self.check_cycles(nodes, input, visiting, done)?;
+                }
+            }
+        }
+        visiting.remove(node.name.as_str());
+        done.insert(node.name.as_str());
+        Ok(())
+    }
+    /// builds the filter of `input` into `built` if it isn't built yet
+    fn build(
+        &self,
+        nodes: &HashMap<&str, &FilterNode>,
+        input: &FilterInput,
+        built: &mut HashMap<String, ImageFilter>,
+    ) -> Result<(), FilterGraphError> {
+        let name = match input {
+            FilterInput::SourceGraphic => return Ok(()),
+            FilterInput::SourceAlpha => "SourceAlpha",
+            FilterInput::Node(name) => name.as_str(),
+        };
+        if built.contains_key(name) {
+            return Ok(());
+        }
+        let FilterInput::Node(name) = input else {
+            let filter = ImageFilter::new_color_filter(
+                &mut ColorFilter::new_color_matrix(&SOURCE_ALPHA_MATRIX),
+                None,
+                None,
+            );
+            built.insert(name.to_string(), filter);
+            return Ok(());
+        };
+        let node = nodes
+            .get(name.as_str())
+            .ok_or_else(|| FilterGraphError::UnknownOutput(name.to_string()))?;
+        for input in node.op.inputs() {
+            self.build(nodes, input, built)?;
+        }
+        let filter = Self::build_node(node, built)?;
+        built.insert(name.to_string(), filter);
+        Ok(())
+    }
+    /// the inputs of `node` must be built already
+    fn build_node(
+        node: &FilterNode,
+        built: &HashMap<String, ImageFilter>,
+    ) -> Result<ImageFilter, FilterGraphError> {
+        // None is the source graphic
+        let get = |input: &FilterInput| match input {
+            FilterInput::SourceGraphic => None,
+            FilterInput::SourceAlpha => built.get("SourceAlpha"),
+            FilterInput::Node(name) => built.get(name),
+        };
+        let crop = node.crop_rect.as_ref();
+        let invalid = || FilterGraphError::InvalidParameters(node.name.clone());
+        let filter = match &node.op {
+            FilterOp::Blur {
+                sigma_x,
+                sigma_y,
+                input,
+            } => ImageFilter::new_blur(
+                *sigma_x,
+                *sigma_y,
+                ShaderTileMode::DECAL_SK_SHADER_TILEMODE,
+                get(input),
+                crop,
+            ),
+            FilterOp::Offset { dx, dy, input } => {
+                ImageFilter::new_offset(*dx, *dy, get(input), crop).ok_or_else(invalid)?
+            }
+            FilterOp::ColorMatrix { matrix, input } => ImageFilter::new_color_filter(
+                &mut ColorFilter::new_color_matrix(matrix),
+                get(input),
+                crop,
+            ),
+            FilterOp::Blend {
+                mode,
+                background,
+                foreground,
+            } => ImageFilter::new_blend(*mode, get(background), get(foreground), crop),
+            FilterOp::Merge { inputs } => {
+                let filters: Vec<Option<&ImageFilter>> = inputs.iter().map(get).collect();
+                ImageFilter::new_merge(&filters, crop)
+            }
+            FilterOp::Dilate {
+                radius_x,
+                radius_y,
+                input,
+            } => ImageFilter::new_dilate(*radius_x, *radius_y, get(input), crop)
+                .ok_or_else(invalid)?,
+            FilterOp::Erode {
+                radius_x,
+                radius_y,
+                input,
+            } => ImageFilter::new_erode(*radius_x, *radius_y, get(input), crop)
+                .ok_or_else(invalid)?,
+            FilterOp::DiffuseLighting {
+                light,
+                color,
+                surface_scale,
+                kd,
+                input,
+            } => {
+                let input = get(input);
+                match light {
+                    Light::Distant { direction } => ImageFilter::new_distant_lit_diffuse(
+                        direction,
+                        *color,
+                        *surface_scale,
+                        *kd,
+                        input,
+                        crop,
+                    ),
+                    Light::Point { location } => ImageFilter::new_point_lit_diffuse(
+                        location,
+                        *color,
+                        *surface_scale,
+                        *kd,
+                        input,
+                        crop,
+                    ),
+                    Light::Spot {
+                        location,
+                        target,
+                        specular_exponent,
+                        cutoff_angle,
+                    } => ImageFilter::new_spot_lit_diffuse(
+                        location,
+                        target,
+                        *specular_exponent,
+                        *cutoff_angle,
+                        *color,
+                        *surface_scale,
+                        *kd,
+                        input,
+                        crop,
+                    ),
+                }
+                .ok_or_else(invalid)?
+            }
+            FilterOp::SpecularLighting {
+                light,
+                color,
+                surface_scale,
+                ks,
+                shininess,
+                input,
+            } => {
+                let input = get(input);
+                match light {
+                    Light::Distant { direction } => ImageFilter::new_distant_lit_specular(
+                        direction,
+                        *color,
+                        *surface_scale,
+                        *ks,
+                        *shininess,
+                        input,
+                        crop,
+                    ),
+                    Light::Point { location } => ImageFilter::new_point_lit_specular(
+                        location,
+                        *color,
+                        *surface_scale,
+                        *ks,
+                        *shininess,
+                        input,
+                        crop,
+                    ),
+                    Light::Spot {
+                        location,
+                        target,
+                        specular_exponent,
+                        cutoff_angle,
+                    } => ImageFilter::new_spot_lit_specular(
+                        location,
+                        target,
+                        *specular_exponent,
+                        *cutoff_angle,
+                        *color,
+                        *surface_scale,
+                        *ks,
+                        *shininess,
+                        input,
+                        crop,
+                    ),
+                }
+                .ok_or_else(invalid)?
+            }
+        };
+        Ok(filter)
+    }
+    /// parses the text format described in [FilterGraph]. The graph is not validated.
+    pub fn parse(text: &str) -> Result<Self, FilterGraphError> {
+        let mut graph = Self::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: String| FilterGraphError::Parse {
+                line: index + 1,
+                message,
+            };
+            // `output = ...` is a node named output (and rejected), not the output directive
+            let Some((name, rest)) = line.split_once('=') else {
+                let output = line
+                    .strip_prefix("output ")
+                    .map(str::trim)
+                    .ok_or_else(|| error("expected `<name> = <op> ...`".to_string()))?;
+                if !is_valid_name(output) {
+                    return Err(error(format!("invalid output name {output:?}")));
+                }
+                graph.set_output(output);
+                continue;
+            };
+            let name = name.trim();
+            let mut words = rest.split_whitespace();
+            let op = words
+                .next()
+                .ok_or_else(|| error("missing op".to_string()))?;
+            let mut args = HashMap::new();
+            for word in words {
+                let (key, value) = word
+                    .split_once('=')
+                    .ok_or_else(|| error(format!("expected `<key>=<value>`, found {word}")))?;
+                if args.insert(key, value).is_some() {
+                    return Err(error(format!("duplicate argument {key}")));
+                }
+            }
+            let previous = graph.nodes.last().map_or(FilterInput::SourceGraphic, |n| {
+                FilterInput::Node(n.name.clone())
+            });
+            let mut args = Args { args, previous };
+            let op = args.parse_op(op).map_err(error)?;
+            let crop_rect = args
+                .numbers_opt("crop", 4, 4)
+                .map_err(error)?
+                .map(|v| Rect::new(v[0], v[1], v[2], v[3]));
+            if let Some(key) = args.args.keys().next() {
+                return Err(error(format!("unknown argument {key}")));
+            }
+            graph
+                .add_node(name, op, crop_rect)
+                .map_err(|e| error(e.to_string()))?;
+        }
+        Ok(graph)
+    }
+}
+impl FromStr for FilterGraph {
+    type Err = FilterGraphError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// remaining arguments of a line. Arguments are removed as they are used.
+struct Args<'a> {
+    args: HashMap<&'a str, &'a str>,
+    previous: FilterInput,
+}
+impl<'a> Args<'a> {
+    fn parse_op(&mut self, op: &str) -> Result<FilterOp, String> {
+        Ok(match op {
+            "blur" => {
+                let (sigma_x, sigma_y) = self.pair("sigma")?;
+                FilterOp::Blur {
+                    sigma_x,
+                    sigma_y,
+                    input: self.input("in"),
+                }
+            }
+            "offset" => {
+                let d = self.numbers("d", 2, 2)?;
+                FilterOp::Offset {
+                    dx: d[0],
+                    dy: d[1],
+                    input: self.input("in"),
+                }
+            }
+            "color_matrix" => {
+                let values = self.numbers("values", 20, 20)?;
+                let mut matrix = [0.0; 20];
+                matrix.copy_from_slice(&values);
+                FilterOp::ColorMatrix {
+                    matrix,
+                    input: self.input("in"),
+                }
+            }
+            "blend" => {
+                let mode = self.required("mode")?;
+                FilterOp::Blend {
+                    mode: parse_blend_mode(mode).ok_or(format!("unknown blend mode {mode}"))?,
+                    foreground: self.input("in"),
+                    background: self.input("in2"),
+                }
+            }
+            "merge" => FilterOp::Merge {
+                inputs: self
+                    .required("in")?
+                    .split(',')
+                    .map(FilterInput::from)
+                    .collect(),
+            },
+            "dilate" | "erode" => {
+                let (radius_x, radius_y) = self.pair("radius")?;
+                let input = self.input("in");
+                if op == "dilate" {
+                    FilterOp::Dilate {
+                        radius_x,
+                        radius_y,
+                        input,
+                    }
+                } else {
+                    FilterOp::Erode {
+                        radius_x,
+                        radius_y,
+                        input,
+                    }
+                }
+            }
+            "diffuse" => FilterOp::DiffuseLighting {
+                light: self.light()?,
+                color: self.color()?,
+                surface_scale: self.number("surface_scale")?,
+                kd: self.number("kd")?,
+                input: self.input("in"),
+            },
+            "specular" => FilterOp::SpecularLighting {
+                light: self.light()?,
+                color: self.color()?,
+                surface_scale: self.number("surface_scale")?,
+                ks: self.number("ks")?,
+                shininess: self.number("shininess")?,
+                input: self.input("in"),
+            },
+            op => return Err(format!("unknown op {op}")),
+        })
+    }
+    fn required(&mut self, key: &str) -> Result<&'a str, String> {
+        self.args
+            .remove(key)
+            .ok_or_else(|| format!("missing argument {key}"))
+    }
+    fn input(&mut self, key: &str) -> FilterInput {
+        self.args
+            .remove(key)
+            .map_or_else(|| self.previous.clone(), FilterInput::from)
+    }
+    fn numbers_opt(
+        &mut self,
+        key: &str,
+        min: usize,
+        max: usize,
+    ) -> Result<Option<Vec<f32>>, String> {
+        let Some(value) = self.args.remove(key) else {
+            return Ok(None);
+        };
+        let numbers = value
+            .split(',')
+            .map(|v| match v.parse::<f32>() {
+                Ok(n) if n.is_finite() => Ok(n),
+                _ => Err(format!("{key}: {v} is not a finite number")),
+            })
+            .collect::<Result<Vec<f32>, String>>()?;
+        if numbers.len() < min || numbers.len() > max {
+            return Err(if min == max {
+                format!("{key} needs {min} numbers")
+            } else {
+                format!("{key} needs {min} to {max} numbers")
+            });
+        }
+        Ok(Some(numbers))
+    }
+    fn numbers(&mut self, key: &str, min: usize, max: usize) -> Result<Vec<f32>, String> {
+        self.numbers_opt(key, min, max)?
+            .ok_or_else(|| format!("missing argument {key}"))
+    }
+    fn number(&mut self, key: &str) -> Result<f32, String> {
+        Ok(self.numbers(key, 1, 1)?[0])
+    }
+    /// `x` or `x,y`. A single number is used for both.
+    fn pair(&mut self, key: &str) -> Result<(f32, f32), String> {
+        let v = self.numbers(key, 1, 2)?;
+        Ok((v[0], *v.get(1).unwrap_or(&v[0])))
+    }
+    fn point3(&mut self, key: &str) -> Result<Option<Point3>, String> {
+        Ok(self.numbers_opt(key, 3, 3)?.map(|v| Point3 {
+            x: v[0],
+            y: v[1],
+            z: v[2],
+        }))
+    }
+    fn light(&mut self) -> Result<Light, String> {
+        if let Some(direction) = self.point3("distant")? {
+            return Ok(Light::Distant { direction });
+        }
+        if let Some(location) = self.point3("point")? {
+            return Ok(Light::Point { location });
+        }
+        if let Some(location) = self.point3("spot")? {
+            return Ok(Light::Spot {
+                location,
+                target: self.point3("target")?.ok_or("missing argument target")?,
+                specular_exponent: self.number("exponent")?,
+                cutoff_angle: self.number("cutoff")?,
+            });
+        }
+        Err("missing light. expected distant=, point= or spot=".to_string())
+    }
+    /// `#rrggbb` or `#aarrggbb`. white if missing.
+    fn color(&mut self) -> Result<Color, String> {
+        let Some(value) = self.args.remove("color") else {
+            return Ok(Color::WHITE);
+        };
+        let hex = value
+            .strip_prefix('#')
+            .ok_or_else(|| format!("color {value} must start with #"))?;
+        let parsed = u32::from_str_radix(hex, 16).map_err(|_| format!("invalid color {value}"));
+        match hex.len() {
+            6 => Ok(Color::from_u32(0xFF000000 | parsed?)),
+            8 => Ok(Color::from_u32(parsed?)),
+            _ => Err(format!("invalid color {value}")),
+        }
+    }
+}
+
+/// see [FilterGraph::add_node]
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        && !matches!(name, "SourceGraphic" | "SourceAlpha" | "output")
+}
+
+fn parse_blend_mode(name: &str) -> Option<BlendMode> {
+    use crate::bindings::sk_blendmode_t::*;
+    Some(match name {
+        "clear" => CLEAR_SK_BLENDMODE,
+        "src" => SRC_SK_BLENDMODE,
+        "dst" => DST_SK_BLENDMODE,
+        "srcover" => SRCOVER_SK_BLENDMODE,
+        "dstover" => DSTOVER_SK_BLENDMODE,
+        "srcin" => SRCIN_SK_BLENDMODE,
+        "dstin" => DSTIN_SK_BLENDMODE,
+        "srcout" => SRCOUT_SK_BLENDMODE,
+        "dstout" => DSTOUT_SK_BLENDMODE,
+        "srcatop" => SRCATOP_SK_BLENDMODE,
+        "dstatop" => DSTATOP_SK_BLENDMODE,
+        "xor" => XOR_SK_BLENDMODE,
+        "plus" => PLUS_SK_BLENDMODE,
+        "modulate" => MODULATE_SK_BLENDMODE,
+        "screen" => SCREEN_SK_BLENDMODE,
+        "overlay" => OVERLAY_SK_BLENDMODE,
+        "darken" => DARKEN_SK_BLENDMODE,
+        "lighten" => LIGHTEN_SK_BLENDMODE,
+        "colordodge" => COLORDODGE_SK_BLENDMODE,
+        "colorburn" => COLORBURN_SK_BLENDMODE,
+        "hardlight" => HARDLIGHT_SK_BLENDMODE,
+        "softlight" => SOFTLIGHT_SK_BLENDMODE,
+        "difference" => DIFFERENCE_SK_BLENDMODE,
+        "exclusion" => EXCLUSION_SK_BLENDMODE,
+        "multiply" => MULTIPLY_SK_BLENDMODE,
+        "hue" => HUE_SK_BLENDMODE,
+        "saturation" => SATURATION_SK_BLENDMODE,
+        "color" => COLOR_SK_BLENDMODE,
+        "luminosity" => LUMINOSITY_SK_BLENDMODE,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FilterGraph, FilterGraphError, FilterInput, FilterOp};
+
+    fn blur(input: &str) -> FilterOp {
+        FilterOp::Blur {
+            sigma_x: 1.0,
+            sigma_y: 1.0,
+            input: FilterInput::from(input),
+        }
+    }
+    fn parse_error_line(text: &str) -> usize {
+        match FilterGraph::parse(text) {
+            Err(FilterGraphError::Parse { line, .. }) => line,
+            other => panic!("expected a parse error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    pub fn test_parse_well_formed() {
+        let graph: FilterGraph = "
+            # drop shadow
+            shadow = blur sigma=4 in=SourceAlpha
+            moved = offset d=3,-3 crop=0,0,100,100
+
+            result = merge in=moved,SourceGraphic
+            unused = blend mode=multiply in=shadow in2=result
+            output result
+        "
+        .parse()
+        .expect("failed to parse graph");
+        assert_eq!(graph.validate(), Ok(()));
+        assert_eq!(graph.get_nodes().len(), 4);
+        assert_eq!(graph.get_output(), Some("result"));
+        let moved = graph.get_node("moved").expect("missing node");
+        // `in` defaults to the previous node
+        assert!(matches!(
+            &moved.op,
+            FilterOp::Offset { dx, dy, input: FilterInput::Node(input) }
+                if *dx == 3.0 && *dy == -3.0 && input == "shadow"
+        ));
+        assert!(moved.crop_rect.is_some());
+        assert!(matches!(
+            &graph.get_node("result").expect("missing node").op,
+            FilterOp::Merge { inputs } if inputs.len() == 2 && inputs[1] == FilterInput::SourceGraphic
+        ));
+        // without an output directive, the last node is the output
+        let graph = FilterGraph::parse("a = blur sigma=1\nb = offset d=1,1").unwrap();
+        assert_eq!(graph.get_output(), Some("b"));
+    }
+
+    #[test]
+    pub fn test_unknown_input() {
+        let graph = FilterGraph::parse("a = blur sigma=1 in=missing").unwrap();
+        assert_eq!(
+            graph.validate(),
+            Err(FilterGraphError::MissingInput {
+                node: "a".to_string(),
+                input: "missing".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    pub fn test_cycle() {
+        let mut graph = FilterGraph::new();
+        graph.add_node("a", blur("c"), None).unwrap();
+        graph.add_node("b", blur("a"), None).unwrap();
+        graph.add_node("c", blur("b"), None).unwrap();
+        graph.add_node("d", blur("SourceGraphic"), None).unwrap();
+        assert!(matches!(graph.validate(), Err(FilterGraphError::Cycle(_))));
+        assert!(matches!(graph.compile(), Err(FilterGraphError::Cycle(_))));
+
+        let graph = FilterGraph::parse("a = blur sigma=1 in=a").unwrap();
+        assert_eq!(
+            graph.validate(),
+            Err(FilterGraphError::Cycle("a".to_string()))
+        );
+    }
+
+    #[test]
+    pub fn test_duplicate_and_invalid_names() {
+        let mut graph = FilterGraph::new();
+        graph.add_node("a", blur("SourceGraphic"), None).unwrap();
+        assert_eq!(
+            graph.add_node("a", blur("SourceGraphic"), None).err(),
+            Some(FilterGraphError::DuplicateNode("a".to_string()))
+        );
+        for name in [
+            "",
+            "SourceAlpha",
+            "SourceGraphic",
+            "output",
+            "two words",
+            "a=b",
+        ] {
+            assert_eq!(
+                graph.add_node(name, blur("a"), None).err(),
+                Some(FilterGraphError::InvalidName(name.to_string()))
+            );
+        }
+        assert_eq!(parse_error_line("a = blur sigma=1\na = blur sigma=2"), 2);
+        // a node named output is not the output directive
+        assert_eq!(
+            parse_error_line("a = blur sigma=1\noutput = blur sigma=2"),
+            2
+        );
+        assert_eq!(parse_error_line("two words = blur sigma=1"), 1);
+        assert_eq!(parse_error_line("a = blur sigma=1\noutput two words"), 2);
+    }
+
+    #[test]
+    pub fn test_missing_output() {
+        assert_eq!(FilterGraph::new().validate(), Err(FilterGraphError::Empty));
+        assert!(matches!(
+            FilterGraph::new().compile(),
+            Err(FilterGraphError::Empty)
+        ));
+        let graph = FilterGraph::parse("a = blur sigma=1\noutput b").unwrap();
+        assert_eq!(
+            graph.validate(),
+            Err(FilterGraphError::UnknownOutput("b".to_string()))
+        );
+    }
+
+    #[test]
+    pub fn test_bad_parameters() {
+        // unknown ops, arguments and values are parse errors
+        assert_eq!(parse_error_line("a = sharpen amount=1"), 1);
+        assert_eq!(parse_error_line("a = blur sigma=1 strength=2"), 1);
+        assert_eq!(parse_error_line("a = blur sigma=1 sigma=2"), 1);
+        assert_eq!(parse_error_line("a = offset d=1"), 1);
+        assert_eq!(parse_error_line("a = blur"), 1);
+        assert_eq!(parse_error_line("a = blend mode=sparkle"), 1);
+        assert_eq!(parse_error_line("a = diffuse kd=1 surface_scale=1"), 1);
+        assert_eq!(
+            parse_error_line("a = diffuse point=1,1,1 color=red kd=1 surface_scale=1"),
+            1
+        );
+        // non-finite numbers are parse errors
+        assert_eq!(parse_error_line("a = offset d=nan,0"), 1);
+        assert_eq!(parse_error_line("a = blur sigma=inf"), 1);
+        assert_eq!(parse_error_line("a = offset d=1,1 crop=0,0,-inf,1"), 1);
+
+        // out of range numbers are reported instead of panicking when compiling
+        let graph = FilterGraph::parse("a = blur sigma=-1,-1").unwrap();
+        let invalid = Err(FilterGraphError::InvalidParameters("a".to_string()));
+        assert_eq!(graph.validate(), invalid);
+        assert!(matches!(
+            graph.compile(),
+            Err(FilterGraphError::InvalidParameters(_))
+        ));
+        let mut graph = FilterGraph::new();
+        graph
+            .add_node(
+                "a",
+                FilterOp::Offset {
+                    dx: f32::NAN,
+                    dy: 0.0,
+                    input: FilterInput::SourceGraphic,
+                },
+                None,
+            )
+            .unwrap();
+        assert_eq!(graph.validate(), invalid);
+        let mut graph = FilterGraph::new();
+        graph
+            .add_node(
+                "a",
+                FilterOp::Dilate {
+                    radius_x: -2.0,
+                    radius_y: 1.0,
+                    input: FilterInput::SourceGraphic,
+                },
+                None,
+            )
+            .unwrap();
+        assert_eq!(graph.validate(), invalid);
+    }
+}
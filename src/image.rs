@@ -1,12 +1,13 @@
 use crate::bindings::*;
 use crate::{
     bitmap::BitMap,
+    codec::Codec,
     color::ColorSpace,
     data::SkiaData,
     gr_context::{DirectContext, GrRecordingContext},
     pixmap::PixMap,
     shader::Shader,
-    AlphaType, ColorType, Matrix, SamplingOptions, ShaderTileMode,
+    AlphaType, CodecResult, ColorType, Matrix, SamplingOptions, ShaderTileMode,
 };
 
 use crate::ImageInfo;
@@ -45,6 +46,15 @@ impl Image {
     pub fn new_from_encoded(data: &SkiaData) -> Option<Self> {
         unsafe { Self::try_from_owned_ptr(sk_image_new_from_encoded(data.as_ptr())) }
     }
+    /// Decodes the image and makes it upright according to its encoded origin (eg: EXIF orientation of photos).
+    /// [Self::new_from_encoded] ignores the origin, so photos taken by phones might appear rotated.
+    ///
+    /// Also returns the matrix which maps coordinates of the encoded image to the returned upright image.
+    pub fn new_from_encoded_oriented(data: &SkiaData) -> Result<(Self, Matrix), CodecResult> {
+        Codec::new_from_data(data)
+            .ok_or(CodecResult::INVALID_INPUT_SK_CODEC_RESULT)?
+            .decode_image_oriented(1.0)
+    }
 
     /*
     pub fn sk_image_new_from_texture(
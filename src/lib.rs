@@ -1,12 +1,17 @@
 use std::ffi::CStr;
 
+pub mod atlas;
 pub mod bindings;
 
 pub mod bitmap;
 pub mod canvas;
+pub mod codec;
 pub mod color;
+pub mod color_matrix;
 pub mod data;
+pub mod document;
 pub mod filter;
+pub mod filter_graph;
 pub mod font;
 pub mod gr_context;
 pub mod image;
@@ -15,21 +20,27 @@ pub mod lua;
 pub mod matrix;
 pub mod paint;
 pub mod paragraph;
+pub mod patch;
 pub mod path;
 pub mod path_effect;
 pub mod picture;
+pub mod pixel_view;
 pub mod pixmap;
 pub mod region;
 pub mod rrect;
+pub mod runtime_effect;
 pub mod shader;
 pub mod skottie;
 pub mod sksg;
 pub mod stream;
 pub mod string;
 pub mod surface;
+pub mod svg;
 pub mod text_blob;
+pub mod text_flow;
 pub mod typeface;
 mod types;
+pub mod vertices;
 use bindings::*;
 pub use color::{Color, PMColor};
 pub use types::*;
@@ -119,6 +119,24 @@ impl Default for Matrix44 {
         }
     }
 }
+/// embeds the 3x3 matrix into a 4x4 matrix which leaves the z coordinate untouched.
+impl From<Matrix> for Matrix44 {
+    fn from(m: Matrix) -> Self {
+        Self {
+            m00: m.scaleX,
+            m01: m.skewX,
+            m03: m.transX,
+            m10: m.skewY,
+            m11: m.scaleY,
+            m13: m.transY,
+            m22: 1.0,
+            m30: m.persp0,
+            m31: m.persp1,
+            m33: m.persp2,
+            ..Default::default()
+        }
+    }
+}
 
 // crate::pod_struct!(pub Matrix, sk_matrix_t {
 //     pub scaleX: f32,
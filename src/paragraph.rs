@@ -504,8 +504,7 @@ impl Paragraph {
                 end,
                 hstyle,
                 wstyle,
-                vec.map(|v| v as *mut Vec<TextBox> as *mut tl_text_box_t)
-                    .unwrap_or(std::ptr::null_mut()),
+                vec.map(|v| v.as_mut_ptr()).unwrap_or(std::ptr::null_mut()),
                 len,
             )
         }
@@ -522,8 +521,7 @@ impl Paragraph {
         unsafe {
             tl_paragraph_get_rects_for_placeholders(
                 self.as_ptr_mut(),
-                vec.map(|v| v as *mut Vec<TextBox> as *mut tl_text_box_t)
-                    .unwrap_or(std::ptr::null_mut()),
+                vec.map(|v| v.as_mut_ptr()).unwrap_or(std::ptr::null_mut()),
                 len,
             )
         }
@@ -0,0 +1,260 @@
+use crate::{color::Color, Point, Rect};
+
+/// A bicubic Coons patch for [crate::canvas::Canvas::draw_patch].
+///
+/// The 12 cubic control points go clockwise around the patch, starting at the top left corner:
+/// top edge `0..=3`, right edge `3..=6`, bottom edge `6..=9` (right to left) and left edge `9..=11, 0` (bottom to top).
+/// Corners are `0` (top left), `3` (top right), `6` (bottom right) and `9` (bottom left).
+///
+/// Colors and texture coordinates are per corner, in the order top left, top right, bottom right, bottom left,
+/// and are interpolated bilinearly over the patch.
+///
+/// To warp an image, use the image's bounds as texture coordinates and its shader as the paint's shader:
+///
+/// ```rust,ignore
+/// let bounds = Rect::new(0.0, 0.0, image.get_width() as f32, image.get_height() as f32);
+/// let mut patch = CoonsPatch::from_rect(&bounds);
+/// patch.cubics[1].y -= 40.0; // bend the top edge
+/// patch.cubics[2].y -= 40.0;
+/// paint.set_shader(image.make_shader(tile, tile, &SamplingOptions::LINEAR, &Matrix::IDENTITY).as_mut());
+/// canvas.draw_patch(&patch, BlendMode::MODULATE_SK_BLENDMODE, &paint);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct CoonsPatch {
+    pub cubics: [Point; 12],
+    pub colors: Option<[Color; 4]>,
+    pub tex_coords: Option<[Point; 4]>,
+}
+impl CoonsPatch {
+    pub fn new(cubics: [Point; 12]) -> Self {
+        Self {
+            cubics,
+            colors: None,
+            tex_coords: None,
+        }
+    }
+    /// a flat patch covering `rect`, with control points at a third of each edge.
+    /// The texture coordinates are the corners of `rect`, so an image shader is drawn unchanged.
+    pub fn from_rect(rect: &Rect) -> Self {
+        let (l, t, r, b) = (rect.left, rect.top, rect.right, rect.bottom);
+        let third = |a: f32, b: f32| (a + (b - a) / 3.0, a + (b - a) * 2.0 / 3.0);
+        let (x1, x2) = third(l, r);
+        let (y1, y2) = third(t, b);
+        let mut patch = Self::new([
+            Point::new(l, t),
+            Point::new(x1, t),
+            Point::new(x2, t),
+            Point::new(r, t),
+            Point::new(r, y1),
+            Point::new(r, y2),
+            Point::new(r, b),
+            Point::new(x2, b),
+            Point::new(x1, b),
+            Point::new(l, b),
+            Point::new(l, y2),
+            Point::new(l, y1),
+        ]);
+        patch.tex_coords = Some(rect_corners(rect));
+        patch
+    }
+    pub fn with_colors(mut self, colors: [Color; 4]) -> Self {
+        self.colors = Some(colors);
+        self
+    }
+    pub fn with_tex_coords(mut self, tex_coords: [Point; 4]) -> Self {
+        self.tex_coords = Some(tex_coords);
+        self
+    }
+    /// top left, top right, bottom right and bottom left
+    pub fn corners(&self) -> [Point; 4] {
+        let c = &self.cubics;
+        [c[0], c[3], c[6], c[9]]
+    }
+    /// the point of the patch at `u` (left to right) and `v` (top to bottom), both in `0..=1`
+    pub fn evaluate(&self, u: f32, v: f32) -> Point {
+        let c = &self.cubics;
+        let top = cubic(c[0], c[1], c[2], c[3], u);
+        let bottom = cubic(c[9], c[8], c[7], c[6], u);
+        let left = cubic(c[0], c[11], c[10], c[9], v);
+        let right = cubic(c[3], c[4], c[5], c[6], v);
+        let corners = bilerp(&self.corners(), u, v);
+        Point::new(
+            (1.0 - v) * top.x + v * bottom.x + (1.0 - u) * left.x + u * right.x - corners.x,
+            (1.0 - v) * top.y + v * bottom.y + (1.0 - u) * left.y + u * right.y - corners.y,
+        )
+    }
+    /// splits the patch into `columns` x `rows` patches (row by row) that together cover exactly the same surface.
+    /// Colors and texture coordinates are interpolated for the new corners.
+    /// Useful to deform parts of a patch independently.
+    pub fn subdivide(&self, columns: usize, rows: usize) -> Vec<CoonsPatch> {
+        let mut patches = Vec::with_capacity(columns * rows);
+        for row in 0..rows {
+            let (v0, v1) = (row as f32 / rows as f32, (row + 1) as f32 / rows as f32);
+            for column in 0..columns {
+                let (u0, u1) = (
+                    column as f32 / columns as f32,
+                    (column + 1) as f32 / columns as f32,
+                );
+                patches.push(self.sub_patch(u0, v0, u1, v1));
+            }
+        }
+        patches
+    }
+    fn sub_patch(&self, u0: f32, v0: f32, u1: f32, v1: f32) -> CoonsPatch {
+        // iso curves of a coons patch are cubics, so 4 samples of each edge give exact control points.
+        let edge = |from: (f32, f32), to: (f32, f32)| {
+            let at =
+                |t: f32| self.evaluate(from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t);
+            cubic_through(at(0.0), at(1.0 / 3.0), at(2.0 / 3.0), at(1.0))
+        };
+        let top = edge((u0, v0), (u1, v0));
+        let right = edge((u1, v0), (u1, v1));
+        let bottom = edge((u1, v1), (u0, v1));
+        let left = edge((u0, v1), (u0, v0));
+        let uv = [(u0, v0), (u1, v0), (u1, v1), (u0, v1)];
+        CoonsPatch {
+            cubics: [
+                top[0], top[1], top[2], right[0], right[1], right[2], bottom[0], bottom[1],
+                bottom[2], left[0], left[1], left[2],
+            ],
+            colors: self
+                .colors
+                .map(|colors| uv.map(|(u, v)| bilerp_color(&colors, u, v))),
+            tex_coords: self
+                .tex_coords
+                .map(|tex| uv.map(|(u, v)| bilerp(&tex, u, v))),
+        }
+    }
+}
+
+fn rect_corners(rect: &Rect) -> [Point; 4] {
+    [
+        Point::new(rect.left, rect.top),
+        Point::new(rect.right, rect.top),
+        Point::new(rect.right, rect.bottom),
+        Point::new(rect.left, rect.bottom),
+    ]
+}
+fn cubic(p0: Point, p1: Point, p2: Point, p3: Point, t: f32) -> Point {
+    let s = 1.0 - t;
+    let (a, b, c, d) = (s * s * s, 3.0 * s * s * t, 3.0 * s * t * t, t * t * t);
+    Point::new(
+        a * p0.x + b * p1.x + c * p2.x + d * p3.x,
+        a * p0.y + b * p1.y + c * p2.y + d * p3.y,
+    )
+}
+/// control points `[p0, c1, c2]` of the cubic that passes through `p0`, `p1` (t = 1/3), `p2` (t = 2/3) and `p3`.
+/// `p3` is the start of the next edge.
+fn cubic_through(p0: Point, p1: Point, p2: Point, p3: Point) -> [Point; 3] {
+    let solve = |p0: f32, p1: f32, p2: f32, p3: f32| {
+        (
+            (-5.0 * p0 + 18.0 * p1 - 9.0 * p2 + 2.0 * p3) / 6.0,
+            (2.0 * p0 - 9.0 * p1 + 18.0 * p2 - 5.0 * p3) / 6.0,
+        )
+    };
+    let (x1, x2) = solve(p0.x, p1.x, p2.x, p3.x);
+    let (y1, y2) = solve(p0.y, p1.y, p2.y, p3.y);
+    [p0, Point::new(x1, y1), Point::new(x2, y2)]
+}
+/// corners are top left, top right, bottom right, bottom left
+fn bilerp(corners: &[Point; 4], u: f32, v: f32) -> Point {
+    let weights = [(1.0 - u) * (1.0 - v), u * (1.0 - v), u * v, (1.0 - u) * v];
+    let mut p = Point::ZERO;
+    for (corner, w) in corners.iter().zip(weights) {
+        p.x += corner.x * w;
+        p.y += corner.y * w;
+    }
+    p
+}
+fn bilerp_color(corners: &[Color; 4], u: f32, v: f32) -> Color {
+    let weights = [(1.0 - u) * (1.0 - v), u * (1.0 - v), u * v, (1.0 - u) * v];
+    let mut argb = [0.0f32; 4];
+    for (corner, w) in corners.iter().zip(weights) {
+        for (c, component) in argb.iter_mut().zip(corner.into_components()) {
+            *c += component as f32 * w;
+        }
+    }
+    let [a, r, g, b] = argb.map(|c| c.round().clamp(0.0, 255.0) as u8);
+    Color::new(a, r, g, b)
+}
+
+#[cfg(test)]
+mod test {
+    use super::CoonsPatch;
+    use crate::{Color, Point, Rect};
+
+    fn assert_near(a: Point, b: Point) {
+        assert!(
+            (a.x - b.x).abs() < 1e-3 && (a.y - b.y).abs() < 1e-3,
+            "{a:?} != {b:?}"
+        );
+    }
+
+    /// a 90x60 patch with a bent top edge and a bulging right edge
+    fn bent_patch() -> CoonsPatch {
+        let mut patch = CoonsPatch::from_rect(&Rect::new(0.0, 0.0, 90.0, 60.0));
+        patch.cubics[1].y -= 30.0;
+        patch.cubics[2].y += 15.0;
+        patch.cubics[4].x += 20.0;
+        patch.cubics[5].x += 20.0;
+        patch
+    }
+
+    #[test]
+    pub fn test_subdivide_preserves_corners() {
+        let patch = bent_patch();
+        let corners = patch.corners();
+        let parts = patch.subdivide(3, 2);
+        assert_eq!(parts.len(), 6);
+        assert_near(parts[0].corners()[0], corners[0]);
+        assert_near(parts[2].corners()[1], corners[1]);
+        assert_near(parts[5].corners()[2], corners[2]);
+        assert_near(parts[3].corners()[3], corners[3]);
+        // a single part is the patch itself
+        let whole = patch.subdivide(1, 1);
+        for (a, b) in whole[0].cubics.iter().zip(&patch.cubics) {
+            assert_near(*a, *b);
+        }
+    }
+
+    #[test]
+    pub fn test_subdivide_preserves_edge_midpoints() {
+        let patch = bent_patch();
+        let parts = patch.subdivide(2, 2);
+        // the shared corners of the parts are the midpoints of the edges
+        assert_near(parts[0].corners()[1], patch.evaluate(0.5, 0.0));
+        assert_near(parts[1].corners()[2], patch.evaluate(1.0, 0.5));
+        assert_near(parts[3].corners()[3], patch.evaluate(0.5, 1.0));
+        assert_near(parts[0].corners()[3], patch.evaluate(0.0, 0.5));
+        assert_near(parts[0].corners()[2], patch.evaluate(0.5, 0.5));
+        // and each part follows the original edges between them
+        for t in [0.25, 0.5, 0.75] {
+            let half = t / 2.0;
+            assert_near(parts[0].evaluate(t, 0.0), patch.evaluate(half, 0.0));
+            assert_near(parts[1].evaluate(t, 0.0), patch.evaluate(0.5 + half, 0.0));
+            assert_near(parts[1].evaluate(1.0, t), patch.evaluate(1.0, half));
+            assert_near(parts[3].evaluate(1.0, t), patch.evaluate(1.0, 0.5 + half));
+            assert_near(parts[2].evaluate(t, 1.0), patch.evaluate(half, 1.0));
+            assert_near(parts[2].evaluate(0.0, t), patch.evaluate(0.0, 0.5 + half));
+            // neighbours share their edge
+            assert_near(parts[0].evaluate(1.0, t), parts[1].evaluate(0.0, t));
+            assert_near(parts[0].evaluate(t, 1.0), parts[2].evaluate(t, 0.0));
+        }
+    }
+
+    #[test]
+    pub fn test_subdivide_interpolates_corner_data() {
+        let patch = CoonsPatch::from_rect(&Rect::new(0.0, 0.0, 100.0, 100.0)).with_colors([
+            Color::new(0xFF, 0, 0, 0),
+            Color::new(0xFF, 200, 0, 0),
+            Color::new(0xFF, 200, 100, 0),
+            Color::new(0xFF, 0, 100, 0),
+        ]);
+        let parts = patch.subdivide(2, 1);
+        assert_eq!(parts[0].colors.unwrap()[1], Color::new(0xFF, 100, 0, 0));
+        assert_eq!(parts[1].colors.unwrap()[2], Color::new(0xFF, 200, 100, 0));
+        let tex = parts[1].tex_coords.unwrap();
+        assert_near(tex[0], Point::new(50.0, 0.0));
+        assert_near(tex[2], Point::new(100.0, 100.0));
+    }
+}
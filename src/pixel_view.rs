@@ -0,0 +1,382 @@
+use std::marker::PhantomData;
+
+use crate::{color::Color, AlphaType, Color4f, ColorType, ImageInfo};
+
+/// Describes how a pixel of a particular [ColorType] is laid out in memory.
+pub trait PixelFormat {
+    const COLOR_TYPE: ColorType;
+    const BYTES_PER_PIXEL: usize;
+    /// the pixel as stored in memory (premultiplied if the alpha type is premul).
+    type Pixel: Copy;
+    /// `bytes` is exactly [Self::BYTES_PER_PIXEL] long
+    fn read(bytes: &[u8]) -> Self::Pixel;
+    /// `bytes` is exactly [Self::BYTES_PER_PIXEL] long
+    fn write(pixel: Self::Pixel, bytes: &mut [u8]);
+    /// [r, g, b, a] as stored. i.e. no unpremultiplication.
+    fn to_rgba(pixel: Self::Pixel) -> [f32; 4];
+    /// inverse of [Self::to_rgba]. components which can't be stored are dropped.
+    fn from_rgba(rgba: [f32; 4]) -> Self::Pixel;
+}
+/// 8 bits per component in r, g, b, a order.
+pub struct Rgba8888;
+/// 8 bits per component in b, g, r, a order.
+pub struct Bgra8888;
+/// just 8 bits of alpha. color is always black.
+pub struct Alpha8;
+/// half float per component in r, g, b, a order. The pixel is converted to f32 when read.
+pub struct RgbaF16;
+
+fn unorm_to_f32(v: u8) -> f32 {
+    v as f32 / 255.0
+}
+fn f32_to_unorm(v: f32) -> u8 {
+    (v.clamp(0.0, 1.0) * 255.0 + 0.5) as u8
+}
+fn f16_to_f32(h: u16) -> f32 {
+    let sign = ((h >> 15) as u32) << 31;
+    let exp = ((h >> 10) & 0x1F) as u32;
+    let mant = (h & 0x3FF) as u32;
+    let bits = match exp {
+        0 => {
+            // zero or subnormal
+            let v = mant as f32 * (-24f32).exp2();
+            return if sign == 0 { v } else { -v };
+        }
+        0x1F => sign | 0x7F80_0000 | (mant << 13),
+        _ => sign | ((exp + 112) << 23) | (mant << 13),
+    };
+    f32::from_bits(bits)
+}
+fn f32_to_f16(f: f32) -> u16 {
+    let bits = f.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exp = ((bits >> 23) & 0xFF) as i32;
+    let mant = bits & 0x7F_FFFF;
+    if exp == 0xFF {
+        // inf or nan
+        return sign | 0x7C00 | if mant != 0 { 0x200 } else { 0 };
+    }
+    let e = exp - 127 + 15;
+    if e >= 0x1F {
+        return sign | 0x7C00;
+    }
+    if e <= 0 {
+        if e < -10 {
+            return sign;
+        }
+        // subnormal
+        let m = mant | 0x80_0000;
+        let shift = (14 - e) as u32;
+        let round = (m >> (shift - 1)) & 1;
+        return sign | ((m >> shift) + round) as u16;
+    }
+    let round = (mant >> 12) & 1;
+    // rounding may carry into the exponent, which is still correct (up to inf).
+    sign | ((((e as u32) << 10) | (mant >> 13)) + round) as u16
+}
+
+impl PixelFormat for Rgba8888 {
+    const COLOR_TYPE: ColorType = ColorType::RGBA_8888_SK_COLORTYPE;
+    const BYTES_PER_PIXEL: usize = 4;
+    type Pixel = [u8; 4];
+    fn read(bytes: &[u8]) -> Self::Pixel {
+        [bytes[0], bytes[1], bytes[2], bytes[3]]
+    }
+    fn write(pixel: Self::Pixel, bytes: &mut [u8]) {
+        bytes.copy_from_slice(&pixel);
+    }
+    fn to_rgba(pixel: Self::Pixel) -> [f32; 4] {
+        pixel.map(unorm_to_f32)
+    }
+    fn from_rgba(rgba: [f32; 4]) -> Self::Pixel {
+        rgba.map(f32_to_unorm)
+    }
+}
+impl PixelFormat for Bgra8888 {
+    const COLOR_TYPE: ColorType = ColorType::BGRA_8888_SK_COLORTYPE;
+    const BYTES_PER_PIXEL: usize = 4;
+    type Pixel = [u8; 4];
+    fn read(bytes: &[u8]) -> Self::Pixel {
+        [bytes[0], bytes[1], bytes[2], bytes[3]]
+    }
+    fn write(pixel: Self::Pixel, bytes: &mut [u8]) {
+        bytes.copy_from_slice(&pixel);
+    }
+    fn to_rgba(pixel: Self::Pixel) -> [f32; 4] {
+        let [b, g, r, a] = pixel.map(unorm_to_f32);
+        [r, g, b, a]
+    }
+    fn from_rgba(rgba: [f32; 4]) -> Self::Pixel {
+        let [r, g, b, a] = rgba.map(f32_to_unorm);
+        [b, g, r, a]
+    }
+}
+impl PixelFormat for Alpha8 {
+    const COLOR_TYPE: ColorType = ColorType::ALPHA_8_SK_COLORTYPE;
+    const BYTES_PER_PIXEL: usize = 1;
+    type Pixel = u8;
+    fn read(bytes: &[u8]) -> Self::Pixel {
+        bytes[0]
+    }
+    fn write(pixel: Self::Pixel, bytes: &mut [u8]) {
+        bytes[0] = pixel;
+    }
+    fn to_rgba(pixel: Self::Pixel) -> [f32; 4] {
+        [0.0, 0.0, 0.0, unorm_to_f32(pixel)]
+    }
+    fn from_rgba(rgba: [f32; 4]) -> Self::Pixel {
+        f32_to_unorm(rgba[3])
+    }
+}
+impl PixelFormat for RgbaF16 {
+    const COLOR_TYPE: ColorType = ColorType::RGBA_F16_SK_COLORTYPE;
+    const BYTES_PER_PIXEL: usize = 8;
+    type Pixel = [f32; 4];
+    fn read(bytes: &[u8]) -> Self::Pixel {
+        [0, 1, 2, 3].map(|i| f16_to_f32(u16::from_ne_bytes([bytes[i * 2], bytes[i * 2 + 1]])))
+    }
+    fn write(pixel: Self::Pixel, bytes: &mut [u8]) {
+        for (i, c) in pixel.into_iter().enumerate() {
+            bytes[i * 2..i * 2 + 2].copy_from_slice(&f32_to_f16(c).to_ne_bytes());
+        }
+    }
+    fn to_rgba(pixel: Self::Pixel) -> [f32; 4] {
+        pixel
+    }
+    fn from_rgba(rgba: [f32; 4]) -> Self::Pixel {
+        rgba
+    }
+}
+
+/// A typed view over the pixels of a [crate::bitmap::BitMap] or [crate::pixmap::PixMap].
+///
+/// The color type is checked when the view is created, so that reads/writes always use the right layout.
+/// `get_color*`/`set_color*` take care of premultiplication based on the alpha type.
+pub struct PixelView<'a, F: PixelFormat> {
+    pixels: &'a mut [u8],
+    width: i32,
+    height: i32,
+    row_bytes: usize,
+    alpha_type: AlphaType,
+    phantom: PhantomData<F>,
+}
+impl<'a, F: PixelFormat> PixelView<'a, F> {
+    /// None if the color type of `info` is not `F::COLOR_TYPE`, if `row_bytes` is too small for a row
+    /// or if `pixels` is too small to hold all the rows.
+    pub fn new(info: &ImageInfo, pixels: &'a mut [u8], row_bytes: usize) -> Option<Self> {
+        if info.get_color_type() != F::COLOR_TYPE
+            || info.get_width() < 0
+            || info.get_height() < 0
+            || row_bytes < info.min_row_bytes()
+            || pixels.len() < info.compute_byte_size(row_bytes)
+        {
+            return None;
+        }
+        Some(Self {
+            pixels,
+            width: info.get_width(),
+            height: info.get_height(),
+            row_bytes,
+            alpha_type: info.get_alpha_type(),
+            phantom: PhantomData,
+        })
+    }
+    pub fn get_width(&self) -> i32 {
+        self.width
+    }
+    pub fn get_height(&self) -> i32 {
+        self.height
+    }
+    pub fn get_row_bytes(&self) -> usize {
+        self.row_bytes
+    }
+    pub fn get_alpha_type(&self) -> AlphaType {
+        self.alpha_type
+    }
+    fn offset(&self, x: i32, y: i32) -> Option<usize> {
+        ((0..self.width).contains(&x) && (0..self.height).contains(&y))
+            .then(|| y as usize * self.row_bytes + x as usize * F::BYTES_PER_PIXEL)
+    }
+    /// None if (x, y) is out of bounds
+    pub fn get(&self, x: i32, y: i32) -> Option<F::Pixel> {
+        let offset = self.offset(x, y)?;
+        Some(F::read(&self.pixels[offset..offset + F::BYTES_PER_PIXEL]))
+    }
+    /// returns false if (x, y) is out of bounds
+    pub fn set(&mut self, x: i32, y: i32, pixel: F::Pixel) -> bool {
+        let Some(offset) = self.offset(x, y) else {
+            return false;
+        };
+        F::write(pixel, &mut self.pixels[offset..offset + F::BYTES_PER_PIXEL]);
+        true
+    }
+    /// unpremultiplied color at (x, y). None if out of bounds.
+    pub fn get_color4f(&self, x: i32, y: i32) -> Option<Color4f> {
+        let [mut r, mut g, mut b, a] = F::to_rgba(self.get(x, y)?);
+        if self.alpha_type == AlphaType::PREMUL_SK_ALPHATYPE && a > 0.0 {
+            r /= a;
+            g /= a;
+            b /= a;
+        }
+        Some(Color4f {
+            fR: r,
+            fG: g,
+            fB: b,
+            fA: a,
+        })
+    }
+    /// `color` is unpremultiplied. returns false if out of bounds.
+    pub fn set_color4f(&mut self, x: i32, y: i32, color: &Color4f) -> bool {
+        let mut rgba = [color.fR, color.fG, color.fB, color.fA];
+        if self.alpha_type == AlphaType::PREMUL_SK_ALPHATYPE {
+            for c in &mut rgba[..3] {
+                *c *= color.fA;
+            }
+        }
+        self.set(x, y, F::from_rgba(rgba))
+    }
+    /// unpremultiplied color at (x, y). None if out of bounds.
+    pub fn get_color(&self, x: i32, y: i32) -> Option<Color> {
+        self.get_color4f(x, y).map(Color::from)
+    }
+    /// returns false if out of bounds.
+    pub fn set_color(&mut self, x: i32, y: i32, color: Color) -> bool {
+        self.set_color4f(x, y, &color.into())
+    }
+    /// raw bytes of row `y`, without any padding at the end. None if out of bounds
+    pub fn get_row(&self, y: i32) -> Option<&[u8]> {
+        let start = self.offset(0, y)?;
+        Some(&self.pixels[start..start + self.width as usize * F::BYTES_PER_PIXEL])
+    }
+    /// raw bytes of row `y`, without any padding at the end. None if out of bounds
+    pub fn get_row_mut(&mut self, y: i32) -> Option<&mut [u8]> {
+        let start = self.offset(0, y)?;
+        Some(&mut self.pixels[start..start + self.width as usize * F::BYTES_PER_PIXEL])
+    }
+    /// iterates over rows from top to bottom. Each row iterates over pixels from left to right.
+    pub fn rows(&self) -> impl Iterator<Item = impl Iterator<Item = F::Pixel> + '_> + '_ {
+        (0..self.height).map(move |y| {
+            self.get_row(y)
+                .unwrap_or_default()
+                .chunks_exact(F::BYTES_PER_PIXEL)
+                .map(F::read)
+        })
+    }
+    /// iterates over ((x, y), pixel) in row major order.
+    pub fn pixels(&self) -> impl Iterator<Item = ((i32, i32), F::Pixel)> + '_ {
+        self.rows().enumerate().flat_map(|(y, row)| {
+            row.enumerate()
+                .map(move |(x, pixel)| ((x as i32, y as i32), pixel))
+        })
+    }
+    /// sets every pixel to `pixel`
+    pub fn fill(&mut self, pixel: F::Pixel) {
+        for y in 0..self.height {
+            if let Some(row) = self.get_row_mut(y) {
+                for bytes in row.chunks_exact_mut(F::BYTES_PER_PIXEL) {
+                    F::write(pixel, bytes);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{AlphaType, ColorType, ImageInfo};
+
+    use super::{f16_to_f32, f32_to_f16, Alpha8, PixelView, Rgba8888};
+
+    #[test]
+    pub fn test_f16_to_f32() {
+        assert_eq!(f16_to_f32(0x3C00), 1.0);
+        assert_eq!(f16_to_f32(0xC000), -2.0);
+        assert_eq!(f16_to_f32(0x7BFF), 65504.0);
+        assert_eq!(f16_to_f32(0x8000).to_bits(), (-0.0f32).to_bits());
+        // subnormals
+        assert_eq!(f16_to_f32(0x0001), 2f32.powi(-24));
+        assert_eq!(f16_to_f32(0x03FF), 1023.0 * 2f32.powi(-24));
+        assert_eq!(f16_to_f32(0x8001), -(2f32.powi(-24)));
+        assert_eq!(f16_to_f32(0x0400), 2f32.powi(-14));
+        // inf and nan
+        assert_eq!(f16_to_f32(0x7C00), f32::INFINITY);
+        assert_eq!(f16_to_f32(0xFC00), f32::NEG_INFINITY);
+        assert!(f16_to_f32(0x7E00).is_nan());
+    }
+
+    #[test]
+    pub fn test_f32_to_f16() {
+        assert_eq!(f32_to_f16(1.0), 0x3C00);
+        assert_eq!(f32_to_f16(-2.0), 0xC000);
+        assert_eq!(f32_to_f16(0.0), 0x0000);
+        assert_eq!(f32_to_f16(-0.0), 0x8000);
+        assert_eq!(f32_to_f16(65504.0), 0x7BFF);
+        // subnormals, and values too small for a subnormal
+        assert_eq!(f32_to_f16(2f32.powi(-24)), 0x0001);
+        assert_eq!(f32_to_f16(-(2f32.powi(-24))), 0x8001);
+        assert_eq!(f32_to_f16(2f32.powi(-30)), 0x0000);
+        // rounding carries into the exponent
+        assert_eq!(f32_to_f16(2.0 - 2f32.powi(-11)), 0x4000);
+        assert_eq!(f32_to_f16(2f32.powi(-14) - 2f32.powi(-25)), 0x0400);
+        assert_eq!(f32_to_f16(65520.0), 0x7C00);
+        // too large, inf and nan
+        assert_eq!(f32_to_f16(1e6), 0x7C00);
+        assert_eq!(f32_to_f16(-1e6), 0xFC00);
+        assert_eq!(f32_to_f16(f32::INFINITY), 0x7C00);
+        assert_eq!(f32_to_f16(f32::NEG_INFINITY), 0xFC00);
+        let nan = f32_to_f16(f32::NAN);
+        assert_eq!(nan & 0x7C00, 0x7C00);
+        assert_ne!(nan & 0x3FF, 0);
+    }
+
+    #[test]
+    pub fn test_f16_round_trip() {
+        for h in 0..=u16::MAX {
+            if h & 0x7C00 == 0x7C00 {
+                continue; // inf and nan
+            }
+            assert_eq!(f32_to_f16(f16_to_f32(h)), h, "{h:#06x} didn't round trip");
+        }
+    }
+
+    fn info(width: i32, height: i32, color_type: ColorType) -> ImageInfo {
+        let mut info = ImageInfo::default();
+        info.set_width(width);
+        info.set_height(height);
+        info.set_color_type(color_type);
+        info.set_alpha_type(AlphaType::UNPREMUL_SK_ALPHATYPE);
+        info
+    }
+
+    #[test]
+    pub fn test_pixel_view_new() {
+        let rgba = info(4, 3, ColorType::RGBA_8888_SK_COLORTYPE);
+        // the last row doesn't need padding: 2 * 20 + 16
+        let mut pixels = vec![0u8; 56];
+        assert!(PixelView::<Rgba8888>::new(&rgba, &mut pixels, 20).is_some());
+        assert!(PixelView::<Rgba8888>::new(&rgba, &mut pixels[..55], 20).is_none());
+        // row bytes smaller than a row
+        assert!(PixelView::<Rgba8888>::new(&rgba, &mut pixels, 12).is_none());
+        // wrong color type
+        assert!(PixelView::<Alpha8>::new(&rgba, &mut pixels, 20).is_none());
+        let alpha = info(4, 3, ColorType::ALPHA_8_SK_COLORTYPE);
+        assert!(PixelView::<Alpha8>::new(&alpha, &mut pixels[..12], 4).is_some());
+        assert!(PixelView::<Alpha8>::new(&alpha, &mut pixels[..11], 4).is_none());
+    }
+
+    #[test]
+    pub fn test_pixel_view_access() {
+        let rgba = info(2, 2, ColorType::RGBA_8888_SK_COLORTYPE);
+        let mut pixels = vec![0u8; 24];
+        let mut view = PixelView::<Rgba8888>::new(&rgba, &mut pixels, 12).unwrap();
+        assert!(view.set(1, 1, [1, 2, 3, 4]));
+        assert!(!view.set(2, 0, [1, 2, 3, 4]));
+        assert!(!view.set(0, -1, [1, 2, 3, 4]));
+        assert_eq!(view.get(1, 1), Some([1, 2, 3, 4]));
+        assert_eq!(view.get(0, 2), None);
+        view.fill([9, 9, 9, 9]);
+        assert!(view.pixels().all(|(_, p)| p == [9, 9, 9, 9]));
+        // padding at the end of the first row is untouched
+        assert_eq!(&pixels[8..12], &[0, 0, 0, 0]);
+    }
+}
@@ -1,8 +1,15 @@
-use std::{marker::PhantomData, mem::transmute};
+use std::{ffi::CStr, marker::PhantomData, mem::transmute, ops::Deref};
 
 use crate::bindings::*;
 
-use crate::{stream::WStream, ImageInfo, PngEncoderFilterFlags};
+use crate::{
+    color::{Color, ColorSpace, ICCProfile},
+    pixel_view::{PixelFormat, PixelView},
+    stream::{DynamicMemoryStream, WStream},
+    Color4f, IRect, ImageInfo, JpegEncoderAlphaOption, JpegEncoderDownsample, JpegEncoderOptions,
+    PngEncoderFilterFlags, PngEncoderOptions, SamplingOptions, SkiaOptPtr, WebpEncoderCompression,
+    WebpEncoderOptions,
+};
 
 #[repr(transparent)]
 pub struct PixMap<'a, T = ()> {
@@ -14,6 +21,25 @@ impl<'a, T> Drop for PixMap<'a, T> {
         unsafe { sk_pixmap_destructor(self.inner) }
     }
 }
+/// A [PixMap] over pixels that must not be written through it, eg: the pixels of a [crate::surface::Surface].
+///
+/// Only `&PixMap` is reachable, so [PixMap::pixel_view] and the `erase_*` fns can't be used.
+pub struct ReadOnlyPixMap<'a, T = ()>(PixMap<'a, T>);
+impl<'a, T> ReadOnlyPixMap<'a, T> {
+    pub(crate) fn new(pixmap: PixMap<'a, T>) -> Self {
+        Self(pixmap)
+    }
+    /// gives the pixmap back, without any pixels
+    pub fn reset(self) -> PixMap<'static, ()> {
+        self.0.reset()
+    }
+}
+impl<'a, T> Deref for ReadOnlyPixMap<'a, T> {
+    type Target = PixMap<'a, T>;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
 impl Default for PixMap<'static, ()> {
     fn default() -> Self {
         let inner = unsafe { sk_pixmap_new() };
@@ -62,6 +88,118 @@ impl<'a, T> PixMap<'a, T> {
         }
     }
 
+    pub fn get_info(&self) -> ImageInfo {
+        let mut info = ImageInfo::default();
+        unsafe {
+            sk_pixmap_get_info(self.inner, info.as_ptr_mut());
+        }
+        info
+    }
+    pub fn get_row_bytes(&self) -> usize {
+        unsafe { sk_pixmap_get_row_bytes(self.inner) }
+    }
+    pub fn get_colorspace(&self) -> Option<ColorSpace> {
+        unsafe { ColorSpace::try_from_owned_ptr(sk_pixmap_get_colorspace(self.inner)) }
+    }
+    pub fn set_colorspace(&mut self, colorspace: Option<&ColorSpace>) {
+        unsafe { sk_pixmap_set_colorspace(self.inner, colorspace.or_null() as _) }
+    }
+    /// true if all pixels are opaque. returns false if the pixmap has no pixels or an unknown color type.
+    pub fn compute_is_opaque(&self) -> bool {
+        unsafe { sk_pixmap_compute_is_opaque(self.inner) }
+    }
+    /// true if (x, y) is inside the pixmap bounds
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        let info = self.get_info();
+        (0..info.get_width()).contains(&x) && (0..info.get_height()).contains(&y)
+    }
+    /// None if (x, y) is out of bounds.
+    pub fn get_pixel_color(&self, x: i32, y: i32) -> Option<Color> {
+        self.contains(x, y)
+            .then(|| unsafe { Color(sk_pixmap_get_pixel_color(self.inner, x, y)) })
+    }
+    /// None if (x, y) is out of bounds.
+    pub fn get_pixel_color4f(&self, x: i32, y: i32) -> Option<Color4f> {
+        self.contains(x, y).then(|| {
+            let mut color = Color4f::default();
+            unsafe { sk_pixmap_get_pixel_color4f(self.inner, x, y, color.as_ptr_mut()) };
+            color
+        })
+    }
+    /// None if (x, y) is out of bounds.
+    pub fn get_pixel_alphaf(&self, x: i32, y: i32) -> Option<f32> {
+        self.contains(x, y)
+            .then(|| unsafe { sk_pixmap_get_pixel_alphaf(self.inner, x, y) })
+    }
+    /// returns a pixmap that shares pixels with `self`, restricted to `subset` (clipped to our bounds).
+    /// None if the subset doesn't intersect our bounds.
+    ///
+    /// We borrow self mutably, as both pixmaps can write to the same pixels.
+    pub fn extract_subset(&mut self, subset: &IRect) -> Option<PixMap<'_, T>> {
+        let result = PixMap::default();
+        unsafe {
+            sk_pixmap_extract_subset(self.inner, result.inner, subset.as_ptr())
+                .then(|| transmute(result))
+        }
+    }
+    /// copies a rect of pixels starting at (src_x, src_y) into `dst_pixels`, converting to `dst_info` if needed.
+    /// returns false if the rect doesn't intersect our bounds or the conversion is not possible.
+    ///
+    /// Panics if `dst_pixels` is too small for `dst_info` and `dst_row_bytes`.
+    #[must_use]
+    pub fn read_pixels(
+        &self,
+        dst_info: &ImageInfo,
+        dst_pixels: &mut [u8],
+        dst_row_bytes: usize,
+        src_x: i32,
+        src_y: i32,
+    ) -> bool {
+        assert!(dst_row_bytes >= dst_info.min_row_bytes());
+        assert!(dst_pixels.len() >= dst_info.compute_byte_size(dst_row_bytes));
+        unsafe {
+            sk_pixmap_read_pixels(
+                self.inner,
+                dst_info.as_ptr(),
+                dst_pixels.as_mut_ptr() as _,
+                dst_row_bytes,
+                src_x,
+                src_y,
+            )
+        }
+    }
+    /// scales and converts our pixels to fit into `dst`.
+    #[must_use]
+    pub fn scale_pixels<U>(&self, dst: &mut PixMap<'_, U>, sampling: &SamplingOptions) -> bool {
+        unsafe { sk_pixmap_scale_pixels(self.inner, dst.inner, sampling.as_ptr()) }
+    }
+    /// fills `subset` (or the whole pixmap if None) with color.
+    #[must_use]
+    pub fn erase_color(&mut self, color: Color, subset: Option<&IRect>) -> bool {
+        unsafe { sk_pixmap_erase_color(self.inner, color.0, subset.or_null()) }
+    }
+    /// fills `subset` (or the whole pixmap if None) with color.
+    #[must_use]
+    pub fn erase_color4f(&mut self, color: &Color4f, subset: Option<&IRect>) -> bool {
+        unsafe { sk_pixmap_erase_color4f(self.inner, color.as_ptr(), subset.or_null()) }
+    }
+    /// typed view over our pixels. None if we have no pixels or if the color type is not `F::COLOR_TYPE`.
+    pub fn pixel_view<F: PixelFormat>(&mut self) -> Option<PixelView<'_, F>> {
+        let info = self.get_info();
+        let row_bytes = self.get_row_bytes();
+        unsafe {
+            let addr = sk_pixmap_get_writable_addr(self.inner);
+            if addr.is_null() {
+                return None;
+            }
+            let len = info.compute_byte_size(row_bytes);
+            PixelView::new(
+                &info,
+                std::slice::from_raw_parts_mut(addr as _, len),
+                row_bytes,
+            )
+        }
+    }
     /// returns true on success.
     #[must_use]
     pub fn encode_png(
@@ -70,83 +208,458 @@ impl<'a, T> PixMap<'a, T> {
         png_filter_flags: Option<PngEncoderFilterFlags>,
         z_lib_level: Option<i32>,
     ) -> bool {
-        let options = sk_pngencoder_options_t {
-            fFilterFlags: png_filter_flags
-                .unwrap_or(PngEncoderFilterFlags::ALL_SK_PNGENCODER_FILTER_FLAGS),
-            fZLibLevel: z_lib_level.unwrap_or(6),
+        let mut options = PngOptions::default();
+        if let Some(flags) = png_filter_flags {
+            options.filter_flags = flags;
+        }
+        if let Some(level) = z_lib_level {
+            options.z_lib_level = level;
+        }
+        self.encode_png_with_options(stream, &options)
+    }
+    /// returns true on success.
+    ///
+    /// Returns false if any of the comments is not a valid png text chunk (see [PngOptions::comments]).
+    #[must_use]
+    pub fn encode_png_with_options(&self, stream: &mut impl WStream, options: &PngOptions) -> bool {
+        if options.comments.is_empty() {
+            return self.encode_png_raw(stream, options);
+        }
+        // the C api wants comments as a `SkDataTable`, which is not exposed.
+        // So, we encode into memory and insert the text chunks after the IHDR chunk ourselves.
+        let Some(mut bytes) = self.encode_png_raw_to_vec(options) else {
+            return false;
+        };
+        if !insert_png_text_chunks(&mut bytes, &options.comments) {
+            return false;
+        }
+        stream.write(&bytes)
+    }
+    /// encodes into memory and returns the bytes. None on failure.
+    pub fn encode_png_to_vec(&self, options: &PngOptions) -> Option<Vec<u8>> {
+        let mut bytes = self.encode_png_raw_to_vec(options)?;
+        insert_png_text_chunks(&mut bytes, &options.comments).then_some(bytes)
+    }
+    fn encode_png_raw_to_vec(&self, options: &PngOptions) -> Option<Vec<u8>> {
+        let mut stream = DynamicMemoryStream::default();
+        self.encode_png_raw(&mut stream, options)
+            .then(|| stream.detach_as_data().get_bytes().to_vec())
+    }
+    /// ignores comments
+    fn encode_png_raw(&self, stream: &mut impl WStream, options: &PngOptions) -> bool {
+        let options = PngEncoderOptions {
+            fFilterFlags: options.filter_flags,
+            fZLibLevel: options.z_lib_level.clamp(0, 9),
             fComments: std::ptr::null_mut(),
+            fICCProfile: options.icc_profile.map_or(std::ptr::null(), |p| p.inner),
+            fICCProfileDescription: options
+                .icc_profile_description
+                .map_or(std::ptr::null(), |d| d.as_ptr()),
+        };
+        unsafe {
+            sk_pngencoder_encode(
+                stream.borrow_wstream_mut_ptr(),
+                self.inner,
+                options.as_ptr(),
+            )
+        }
+    }
+    /// returns true on success.
+    #[must_use]
+    pub fn encode_jpeg(&self, stream: &mut impl WStream, options: &JpegOptions) -> bool {
+        let options = JpegEncoderOptions {
+            fQuality: options.quality.min(100) as _,
+            fDownsample: options.downsample,
+            fAlphaOption: options.alpha_option,
+            xmpMetadata: std::ptr::null(),
             fICCProfile: std::ptr::null(),
             fICCProfileDescription: std::ptr::null(),
         };
-        unsafe { sk_pngencoder_encode(stream.borrow_wstream_mut_ptr(), self.inner, &options as _) }
+        unsafe {
+            sk_jpegencoder_encode(
+                stream.borrow_wstream_mut_ptr(),
+                self.inner,
+                options.as_ptr(),
+            )
+        }
+    }
+    /// encodes into memory and returns the bytes. None on failure.
+    pub fn encode_jpeg_to_vec(&self, options: &JpegOptions) -> Option<Vec<u8>> {
+        let mut stream = DynamicMemoryStream::default();
+        self.encode_jpeg(&mut stream, options)
+            .then(|| stream.detach_as_data().get_bytes().to_vec())
+    }
+    /// returns true on success.
+    #[must_use]
+    pub fn encode_webp(&self, stream: &mut impl WStream, options: &WebpOptions) -> bool {
+        let options = WebpEncoderOptions {
+            fCompression: options.compression,
+            fQuality: options.quality.clamp(0.0, 100.0),
+            fICCProfile: std::ptr::null(),
+            fICCProfileDescription: std::ptr::null(),
+        };
+        unsafe {
+            sk_webpencoder_encode(
+                stream.borrow_wstream_mut_ptr(),
+                self.inner,
+                options.as_ptr(),
+            )
+        }
+    }
+    /// encodes into memory and returns the bytes. None on failure.
+    pub fn encode_webp_to_vec(&self, options: &WebpOptions) -> Option<Vec<u8>> {
+        let mut stream = DynamicMemoryStream::default();
+        self.encode_webp(&mut stream, options)
+            .then(|| stream.detach_as_data().get_bytes().to_vec())
+    }
+}
+pub struct PngOptions<'a> {
+    pub filter_flags: PngEncoderFilterFlags,
+    /// 0 to 9. higher is smaller but slower. 6 by default.
+    pub z_lib_level: i32,
+    /// key/value pairs written as text chunks right after the header.
+    ///
+    /// keys must be 1 to 79 bytes of printable latin-1 (ascii) without leading/trailing spaces,
+    /// and must not contain NUL. values that fit in latin-1 are written as `tEXt`, others as utf-8 `iTXt`.
+    pub comments: Vec<(String, String)>,
+    /// embedded profile. If None, skia derives one from the pixmap's colorspace (if it has one).
+    /// Use [crate::color::ColorSpace::to_profile] to embed a different colorspace.
+    pub icc_profile: Option<&'a ICCProfile<'a>>,
+    /// description stored inside the embedded profile.
+    pub icc_profile_description: Option<&'a CStr>,
+}
+impl Default for PngOptions<'_> {
+    fn default() -> Self {
+        Self {
+            filter_flags: PngEncoderFilterFlags::ALL_SK_PNGENCODER_FILTER_FLAGS,
+            z_lib_level: 6,
+            comments: vec![],
+            icc_profile: None,
+            icc_profile_description: None,
+        }
+    }
+}
+impl<'a> PngOptions<'a> {
+    pub fn with_comment(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.comments.push((key.into(), value.into()));
+        self
+    }
+    pub fn with_icc_profile(mut self, profile: &'a ICCProfile<'a>) -> Self {
+        self.icc_profile = Some(profile);
+        self
+    }
+}
+fn is_valid_png_keyword(key: &str) -> bool {
+    (1..=79).contains(&key.len())
+        && !key.starts_with(' ')
+        && !key.ends_with(' ')
+        && !key.contains("  ")
+        && key.bytes().all(|b| (b' '..=b'~').contains(&b))
+}
+fn png_crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &b in bytes {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+/// inserts `tEXt`/`iTXt` chunks after IHDR. returns false if any comment is invalid or
+/// if `png` doesn't start with a signature + IHDR.
+fn insert_png_text_chunks(png: &mut Vec<u8>, comments: &[(String, String)]) -> bool {
+    if comments.is_empty() {
+        return true;
+    }
+    const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+    if png.len() < 8 + 8 || png[..8] != SIGNATURE || &png[12..16] != b"IHDR" {
+        return false;
+    }
+    let ihdr_len = u32::from_be_bytes([png[8], png[9], png[10], png[11]]) as usize;
+    // signature + length + type + data + crc
+    let insert_at = 8 + 4 + 4 + ihdr_len + 4;
+    if png.len() < insert_at {
+        return false;
+    }
+    let mut chunks = vec![];
+    for (key, value) in comments {
+        if !is_valid_png_keyword(key) || value.contains('\0') {
+            return false;
+        }
+        let mut chunk = vec![];
+        if value.chars().all(|c| (c as u32) <= 0xFF) {
+            chunk.extend_from_slice(b"tEXt");
+            chunk.extend_from_slice(key.as_bytes());
+            chunk.push(0);
+            chunk.extend(value.chars().map(|c| c as u8));
+        } else {
+            chunk.extend_from_slice(b"iTXt");
+            chunk.extend_from_slice(key.as_bytes());
+            // separator, compression flag, compression method, empty language tag and translated keyword
+            chunk.extend_from_slice(&[0, 0, 0, 0, 0]);
+            chunk.extend_from_slice(value.as_bytes());
+        }
+        let Ok(len) = u32::try_from(chunk.len() - 4) else {
+            return false;
+        };
+        chunks.extend_from_slice(&len.to_be_bytes());
+        chunks.extend_from_slice(&chunk);
+        chunks.extend_from_slice(&png_crc32(&chunk).to_be_bytes());
+    }
+    png.splice(insert_at..insert_at, chunks);
+    true
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JpegOptions {
+    /// 0 to 100. higher is better quality and bigger size. values above 100 are clamped.
+    pub quality: u8,
+    /// chroma subsampling. 420 produces the smallest files.
+    pub downsample: JpegEncoderDownsample,
+    /// jpeg doesn't support transparency, so alpha is either ignored or the image is blended on black.
+    pub alpha_option: JpegEncoderAlphaOption,
+}
+impl Default for JpegOptions {
+    fn default() -> Self {
+        Self {
+            quality: 100,
+            downsample: JpegEncoderDownsample::DOWNSAMPLE_420_SK_JPEGENCODER_DOWNSAMPLE,
+            alpha_option: JpegEncoderAlphaOption::IGNORE_SK_JPEGENCODER_ALPHA_OPTION,
+        }
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WebpOptions {
+    pub compression: WebpEncoderCompression,
+    /// 0.0 to 100.0. For lossy compression, this is the visual quality (higher is better and bigger).
+    /// For lossless compression, this is the effort (higher is smaller but slower).
+    pub quality: f32,
+}
+impl Default for WebpOptions {
+    fn default() -> Self {
+        Self {
+            compression: WebpEncoderCompression::LOSSY_SK_WEBPENCODER_COMPTRESSION,
+            quality: 100.0,
+        }
+    }
+}
+impl WebpOptions {
+    pub fn lossless() -> Self {
+        Self {
+            compression: WebpEncoderCompression::LOSSLESS_SK_WEBPENCODER_COMPTRESSION,
+            quality: 75.0,
+        }
     }
 }
 /*
-
-
-pub fn sk_pixmap_set_colorspace(cpixmap: *mut sk_pixmap_t, colorspace: *mut sk_colorspace_t);
-pub fn sk_pixmap_extract_subset(
-    cpixmap: *const sk_pixmap_t,
-    result: *mut sk_pixmap_t,
-    subset: *const sk_irect_t,
-) -> bool;
-pub fn sk_pixmap_get_info(cpixmap: *const sk_pixmap_t, cinfo: *mut sk_imageinfo_t);
-pub fn sk_pixmap_get_row_bytes(cpixmap: *const sk_pixmap_t) -> usize;
-pub fn sk_pixmap_get_colorspace(cpixmap: *const sk_pixmap_t) -> *mut sk_colorspace_t;
-pub fn sk_pixmap_compute_is_opaque(cpixmap: *const sk_pixmap_t) -> bool;
-pub fn sk_pixmap_get_pixel_color(
-    cpixmap: *const sk_pixmap_t,
-    x: ::std::os::raw::c_int,
-    y: ::std::os::raw::c_int,
-) -> sk_color_t;
-pub fn sk_pixmap_get_pixel_color4f(
-    cpixmap: *const sk_pixmap_t,
-    x: ::std::os::raw::c_int,
-    y: ::std::os::raw::c_int,
-    color: *mut sk_color4f_t,
-);
-pub fn sk_pixmap_get_pixel_alphaf(
-    cpixmap: *const sk_pixmap_t,
-    x: ::std::os::raw::c_int,
-    y: ::std::os::raw::c_int,
-) -> f32;
-pub fn sk_pixmap_get_writable_addr(cpixmap: *const sk_pixmap_t) -> *mut ::std::os::raw::c_void;
 pub fn sk_pixmap_get_writeable_addr_with_xy(
     cpixmap: *const sk_pixmap_t,
     x: ::std::os::raw::c_int,
     y: ::std::os::raw::c_int,
 ) -> *mut ::std::os::raw::c_void;
-pub fn sk_pixmap_read_pixels(
-    cpixmap: *const sk_pixmap_t,
-    dstInfo: *const sk_imageinfo_t,
-    dstPixels: *mut ::std::os::raw::c_void,
-    dstRowBytes: usize,
-    srcX: ::std::os::raw::c_int,
-    srcY: ::std::os::raw::c_int,
-) -> bool;
-pub fn sk_pixmap_scale_pixels(
-    cpixmap: *const sk_pixmap_t,
-    dst: *const sk_pixmap_t,
-    sampling: *const sk_sampling_options_t,
-) -> bool;
-pub fn sk_pixmap_erase_color(
-    cpixmap: *const sk_pixmap_t,
-    color: sk_color_t,
-    subset: *const sk_irect_t,
-) -> bool;
-pub fn sk_pixmap_erase_color4f(
-    cpixmap: *const sk_pixmap_t,
-    color: *const sk_color4f_t,
-    subset: *const sk_irect_t,
-) -> bool;
-pub fn sk_webpencoder_encode(
-    dst: *mut sk_wstream_t,
-    src: *const sk_pixmap_t,
-    options: *const sk_webpencoder_options_t,
-) -> bool;
-pub fn sk_jpegencoder_encode(
-    dst: *mut sk_wstream_t,
-    src: *const sk_pixmap_t,
-    options: *const sk_jpegencoder_options_t,
-) -> bool;
 */
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        codec::Codec, color::ColorSpace, data::SkiaData, ColorspaceTransferFn, ColorspaceXyz,
+        ImageInfo,
+    };
+
+    use super::{insert_png_text_chunks, is_valid_png_keyword, png_crc32, PixMap, PngOptions};
+
+    #[test]
+    pub fn test_png_metadata_round_trip() {
+        let mut transfer_fn = ColorspaceTransferFn::default();
+        transfer_fn.set_srgb();
+        let mut gamut = ColorspaceXyz::default();
+        gamut.set_display_p3();
+        let p3 = ColorSpace::new_rgb(&transfer_fn, &gamut);
+        let profile = p3.to_profile();
+
+        let mut info = ImageInfo::default();
+        info.set_width(4);
+        info.set_height(4);
+        let row_bytes = info.min_row_bytes();
+        let mut pixels = vec![255u8; info.compute_byte_size(row_bytes)];
+        let pixmap = PixMap::<()>::new_with_params(&info, &mut pixels, row_bytes);
+
+        let options = PngOptions::default()
+            .with_comment("Software", "ckia")
+            .with_comment("Title", "gamut \u{1F308}")
+            .with_icc_profile(&profile);
+        let bytes = pixmap
+            .encode_png_to_vec(&options)
+            .expect("failed to encode png");
+        for needle in [
+            &b"tEXtSoftware\0ckia"[..],
+            &b"iTXtTitle\0"[..],
+            &b"iCCP"[..],
+        ] {
+            assert!(
+                bytes.windows(needle.len()).any(|w| w == needle),
+                "encoded png is missing a chunk"
+            );
+        }
+
+        let codec =
+            Codec::new_from_data(&SkiaData::new_with_copy(&bytes)).expect("failed to decode png");
+        let decoded_info = codec.get_info();
+        let decoded = decoded_info
+            .get_colorspace()
+            .expect("decoded png has no colorspace")
+            .to_profile()
+            .get_to_xyzd50()
+            .expect("decoded profile has no gamut");
+        let expected = [
+            gamut.fM00, gamut.fM01, gamut.fM02, gamut.fM10, gamut.fM11, gamut.fM12, gamut.fM20,
+            gamut.fM21, gamut.fM22,
+        ];
+        let actual = [
+            decoded.fM00,
+            decoded.fM01,
+            decoded.fM02,
+            decoded.fM10,
+            decoded.fM11,
+            decoded.fM12,
+            decoded.fM20,
+            decoded.fM21,
+            decoded.fM22,
+        ];
+        for (e, a) in expected.into_iter().zip(actual) {
+            assert!((e - a).abs() < 0.001, "decoded gamut doesn't match");
+        }
+
+        let invalid = PngOptions::default().with_comment(" bad key", "value");
+        assert!(pixmap.encode_png_to_vec(&invalid).is_none());
+    }
+
+    fn chunk(kind: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut body = kind.to_vec();
+        body.extend_from_slice(data);
+        let mut chunk = (data.len() as u32).to_be_bytes().to_vec();
+        chunk.extend_from_slice(&body);
+        chunk.extend_from_slice(&png_crc32(&body).to_be_bytes());
+        chunk
+    }
+
+    /// signature + IHDR of a 1x1 rgba image + IEND
+    fn minimal_png() -> Vec<u8> {
+        let mut png = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+        png.extend(chunk(b"IHDR", &[0, 0, 0, 1, 0, 0, 0, 1, 8, 6, 0, 0, 0]));
+        png.extend(chunk(b"IEND", &[]));
+        png
+    }
+
+    fn comments(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|&(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    pub fn test_png_crc32() {
+        assert_eq!(png_crc32(b"123456789"), 0xCBF4_3926);
+        // the crc of every IEND chunk
+        assert_eq!(png_crc32(b"IEND"), 0xAE42_6082);
+        assert_eq!(
+            &minimal_png()[33..],
+            &[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82]
+        );
+    }
+
+    #[test]
+    pub fn test_png_keyword() {
+        assert!(is_valid_png_keyword("Software"));
+        assert!(is_valid_png_keyword("Creation Time"));
+        assert!(is_valid_png_keyword(&"k".repeat(79)));
+        assert!(!is_valid_png_keyword(&"k".repeat(80)));
+        assert!(!is_valid_png_keyword(""));
+        assert!(!is_valid_png_keyword(" Title"));
+        assert!(!is_valid_png_keyword("Title "));
+        assert!(!is_valid_png_keyword("Creation  Time"));
+        assert!(!is_valid_png_keyword("Tit\nle"));
+        assert!(!is_valid_png_keyword("Titel\u{e9}"));
+    }
+
+    #[test]
+    pub fn test_png_text_chunks() {
+        let original = minimal_png();
+        // right after the 8 byte signature and the 25 byte IHDR chunk
+        let insert_at = 33;
+
+        let mut png = original.clone();
+        assert!(insert_png_text_chunks(&mut png, &[]));
+        assert_eq!(png, original);
+
+        let mut png = original.clone();
+        assert!(insert_png_text_chunks(
+            &mut png,
+            &comments(&[("Software", "ckia")])
+        ));
+        let text = chunk(b"tEXt", b"Software\0ckia");
+        assert_eq!(&png[insert_at..insert_at + text.len()], &text[..]);
+        assert_eq!(png.len(), original.len() + text.len());
+        assert_eq!(&png[insert_at + text.len()..], &original[insert_at..]);
+
+        // latin-1 values are stored as latin-1 bytes
+        let mut png = original.clone();
+        assert!(insert_png_text_chunks(
+            &mut png,
+            &comments(&[("Author", "Ren\u{e9}e")])
+        ));
+        let text = chunk(b"tEXt", b"Author\0Ren\xE9e");
+        assert_eq!(&png[insert_at..insert_at + text.len()], &text[..]);
+
+        // anything else falls back to utf-8 iTXt, with an empty language and translated keyword
+        let mut png = original.clone();
+        assert!(insert_png_text_chunks(
+            &mut png,
+            &comments(&[("Title", "snow \u{2603}"), ("Software", "ckia")])
+        ));
+        let itxt = chunk(b"iTXt", "Title\0\0\0\0\0snow \u{2603}".as_bytes());
+        let text = chunk(b"tEXt", b"Software\0ckia");
+        assert_eq!(&png[insert_at..insert_at + itxt.len()], &itxt[..]);
+        assert_eq!(
+            &png[insert_at + itxt.len()..insert_at + itxt.len() + text.len()],
+            &text[..]
+        );
+    }
+
+    #[test]
+    pub fn test_png_text_chunks_invalid() {
+        let original = minimal_png();
+        for pairs in [
+            [("", "value")],
+            [(" key", "value")],
+            [("key", "nul\0value")],
+        ] {
+            let mut png = original.clone();
+            assert!(!insert_png_text_chunks(&mut png, &comments(&pairs)));
+            assert_eq!(png, original);
+        }
+        let long_key = "k".repeat(80);
+        let mut png = original.clone();
+        assert!(!insert_png_text_chunks(
+            &mut png,
+            &comments(&[(&long_key, "value")])
+        ));
+        // not a png
+        let mut png = original[8..].to_vec();
+        assert!(!insert_png_text_chunks(
+            &mut png,
+            &comments(&[("key", "value")])
+        ));
+        // truncated IHDR
+        let mut png = original[..20].to_vec();
+        assert!(!insert_png_text_chunks(
+            &mut png,
+            &comments(&[("key", "value")])
+        ));
+    }
+}
@@ -0,0 +1,613 @@
+use std::fmt::Display;
+
+use crate::bindings::*;
+use crate::{
+    data::SkiaData, filter::ColorFilter, shader::Shader, skia_wrapper, string::SkiaString, Color4f,
+    Matrix, RuntimeEffectChild, RuntimeEffectChildType, RuntimeEffectUniformFlags,
+    RuntimeEffectUniformType,
+};
+
+skia_wrapper!(
+    refcnt,
+    RuntimeEffect,
+    sk_runtimeeffect_t,
+    sk_runtimeeffect_unref
+);
+
+/// Same layout as [crate::RuntimeEffectUniform], but flags are a bitmask which may not be a valid enum variant.
+#[repr(C)]
+struct RawUniform {
+    name: *const std::os::raw::c_char,
+    name_length: usize,
+    offset: usize,
+    ty: RuntimeEffectUniformType,
+    count: std::os::raw::c_int,
+    flags: u32,
+}
+impl RawUniform {
+    unsafe fn into_info(self) -> UniformInfo {
+        UniformInfo {
+            name: name_from_raw(self.name, self.name_length),
+            offset: self.offset,
+            ty: self.ty,
+            count: self.count,
+            flags: self.flags,
+        }
+    }
+}
+/// A uniform declared in the sksl of a [RuntimeEffect]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UniformInfo {
+    pub name: String,
+    /// offset in bytes within the uniform data
+    pub offset: usize,
+    pub ty: RuntimeEffectUniformType,
+    /// number of elements for arrays. 1 otherwise.
+    pub count: i32,
+    /// bitmask of [RuntimeEffectUniformFlags]
+    pub flags: u32,
+}
+impl UniformInfo {
+    pub fn is_float(&self) -> bool {
+        use sk_runtimeeffect_uniform_type_t::*;
+        matches!(
+            self.ty,
+            FLOAT_SK_RUNTIMEEFFECT_UNIFORM_TYPE
+                | FLOAT2_SK_RUNTIMEEFFECT_UNIFORM_TYPE
+                | FLOAT3_SK_RUNTIMEEFFECT_UNIFORM_TYPE
+                | FLOAT4_SK_RUNTIMEEFFECT_UNIFORM_TYPE
+                | FLOAT2X2_SK_RUNTIMEEFFECT_UNIFORM_TYPE
+                | FLOAT3X3_SK_RUNTIMEEFFECT_UNIFORM_TYPE
+                | FLOAT4X4_SK_RUNTIMEEFFECT_UNIFORM_TYPE
+        )
+    }
+    /// number of f32 or i32 components in a single element
+    pub fn components(&self) -> usize {
+        use sk_runtimeeffect_uniform_type_t::*;
+        match self.ty {
+            FLOAT_SK_RUNTIMEEFFECT_UNIFORM_TYPE | INT_SK_RUNTIMEEFFECT_UNIFORM_TYPE => 1,
+            FLOAT2_SK_RUNTIMEEFFECT_UNIFORM_TYPE | INT2_SK_RUNTIMEEFFECT_UNIFORM_TYPE => 2,
+            FLOAT3_SK_RUNTIMEEFFECT_UNIFORM_TYPE | INT3_SK_RUNTIMEEFFECT_UNIFORM_TYPE => 3,
+            FLOAT4_SK_RUNTIMEEFFECT_UNIFORM_TYPE | INT4_SK_RUNTIMEEFFECT_UNIFORM_TYPE => 4,
+            FLOAT2X2_SK_RUNTIMEEFFECT_UNIFORM_TYPE => 4,
+            FLOAT3X3_SK_RUNTIMEEFFECT_UNIFORM_TYPE => 9,
+            FLOAT4X4_SK_RUNTIMEEFFECT_UNIFORM_TYPE => 16,
+        }
+    }
+    /// total size in bytes (including all elements of an array)
+    pub fn size_in_bytes(&self) -> usize {
+        self.components() * 4 * self.count.max(0) as usize
+    }
+    pub fn has_flag(&self, flag: RuntimeEffectUniformFlags) -> bool {
+        self.flags & flag as u32 != 0
+    }
+}
+/// A child (`shader`, `colorFilter` or `blender`) declared in the sksl of a [RuntimeEffect]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChildInfo {
+    pub name: String,
+    pub ty: RuntimeEffectChildType,
+    pub index: i32,
+}
+
+impl RuntimeEffect {
+    /// compiles sksl with a `half4 main(float2 coords)` fn. Err contains the compiler errors.
+    pub fn for_shader(sksl: &str) -> Result<Self, String> {
+        Self::compile(sksl, sk_runtimeeffect_make_for_shader)
+    }
+    /// compiles sksl with a `half4 main(half4 color)` fn. Err contains the compiler errors.
+    pub fn for_color_filter(sksl: &str) -> Result<Self, String> {
+        Self::compile(sksl, sk_runtimeeffect_make_for_color_filter)
+    }
+    fn compile(
+        sksl: &str,
+        make: unsafe extern "C" fn(*mut sk_string_t, *mut sk_string_t) -> *mut sk_runtimeeffect_t,
+    ) -> Result<Self, String> {
+        let mut source = SkiaString::new_with_copy(sksl);
+        let mut error = SkiaString::new_empty();
+        unsafe {
+            Self::try_from_owned_ptr(make(source.as_ptr_mut(), error.as_ptr_mut()))
+                .ok_or_else(|| error.get_cstr().to_string_lossy().into_owned())
+        }
+    }
+    /// size of the uniform data expected by [Self::make_shader] and [Self::make_color_filter]
+    pub fn get_uniform_byte_size(&self) -> usize {
+        unsafe { sk_runtimeeffect_get_uniform_byte_size(self.as_ptr()) }
+    }
+    pub fn get_uniforms(&self) -> Vec<UniformInfo> {
+        let len = unsafe { sk_runtimeeffect_get_uniforms_size(self.as_ptr()) };
+        (0..len)
+            .map(|index| unsafe {
+                let mut raw = std::mem::MaybeUninit::<RawUniform>::zeroed();
+                sk_runtimeeffect_get_uniform_from_index(
+                    self.as_ptr(),
+                    index as _,
+                    raw.as_mut_ptr() as _,
+                );
+                raw.assume_init().into_info()
+            })
+            .collect()
+    }
+    pub fn find_uniform(&self, name: &str) -> Option<UniformInfo> {
+        self.get_uniforms().into_iter().find(|u| u.name == name)
+    }
+    /// same as [Self::find_uniform], but skia looks up the name instead of listing all uniforms.
+    pub fn get_uniform_from_name(&self, name: &str) -> Option<UniformInfo> {
+        let uniform = unsafe {
+            let mut raw = std::mem::MaybeUninit::<RawUniform>::zeroed();
+            sk_runtimeeffect_get_uniform_from_name(
+                self.as_ptr(),
+                name.as_ptr() as _,
+                name.len(),
+                raw.as_mut_ptr() as _,
+            );
+            raw.assume_init().into_info()
+        };
+        // unknown names leave the uniform zeroed
+        (uniform.name == name).then_some(uniform)
+    }
+    pub fn get_children(&self) -> Vec<ChildInfo> {
+        let len = unsafe { sk_runtimeeffect_get_children_size(self.as_ptr()) };
+        (0..len)
+            .map(|index| unsafe {
+                let mut raw = RuntimeEffectChild {
+                    fName: std::ptr::null(),
+                    fNameLength: 0,
+                    fType: RuntimeEffectChildType::SHADER_SK_RUNTIMEEFFECT_CHILD_TYPE,
+                    fIndex: 0,
+                };
+                sk_runtimeeffect_get_child_from_index(self.as_ptr(), index as _, raw.as_ptr_mut());
+                ChildInfo {
+                    name: name_from_raw(raw.fName, raw.fNameLength),
+                    ty: raw.fType,
+                    index: raw.fIndex,
+                }
+            })
+            .collect()
+    }
+    pub fn find_child(&self, name: &str) -> Option<ChildInfo> {
+        self.get_children().into_iter().find(|c| c.name == name)
+    }
+    /// `uniforms` must be exactly [Self::get_uniform_byte_size] long, and `children` must have an entry per child.
+    /// Prefer [RuntimeEffectBuilder], which validates everything.
+    ///
+    /// None if the effect is not a shader effect or the inputs don't match.
+    pub fn make_shader(
+        &self,
+        uniforms: &SkiaData,
+        children: &[Option<EffectChild>],
+        local_matrix: Option<&Matrix>,
+    ) -> Option<Shader> {
+        let mut children: Vec<_> = children.iter().map(EffectChild::as_flattenable).collect();
+        unsafe {
+            Shader::try_from_owned_ptr(sk_runtimeeffect_make_shader(
+                self.inner,
+                uniforms.inner,
+                children.as_mut_ptr(),
+                children.len(),
+                local_matrix.map_or(std::ptr::null(), |m| m.as_ptr()),
+            ))
+        }
+    }
+    /// same as [Self::make_shader], but for color filter effects.
+    pub fn make_color_filter(
+        &self,
+        uniforms: &SkiaData,
+        children: &[Option<EffectChild>],
+    ) -> Option<ColorFilter> {
+        let mut children: Vec<_> = children.iter().map(EffectChild::as_flattenable).collect();
+        unsafe {
+            ColorFilter::try_from_owned_ptr(sk_runtimeeffect_make_color_filter(
+                self.inner,
+                uniforms.inner,
+                children.as_mut_ptr(),
+                children.len(),
+            ))
+        }
+    }
+    pub fn builder(&self) -> RuntimeEffectBuilder<'_> {
+        RuntimeEffectBuilder::new(self)
+    }
+}
+/// # Safety
+/// name must be valid for len bytes (or null)
+unsafe fn name_from_raw(name: *const std::os::raw::c_char, len: usize) -> String {
+    if name.is_null() {
+        return String::new();
+    }
+    String::from_utf8_lossy(std::slice::from_raw_parts(name as *const u8, len)).into_owned()
+}
+
+/// An input for the `shader` or `colorFilter` children of a [RuntimeEffect]
+pub enum EffectChild {
+    Shader(Shader),
+    ColorFilter(ColorFilter),
+}
+impl EffectChild {
+    fn get_type(&self) -> RuntimeEffectChildType {
+        match self {
+            EffectChild::Shader(_) => RuntimeEffectChildType::SHADER_SK_RUNTIMEEFFECT_CHILD_TYPE,
+            EffectChild::ColorFilter(_) => {
+                RuntimeEffectChildType::COLOR_FILTER_SK_RUNTIMEEFFECT_CHILD_TYPE
+            }
+        }
+    }
+    fn as_flattenable(child: &Option<Self>) -> *mut sk_flattenable_t {
+        match child {
+            Some(EffectChild::Shader(s)) => s.inner as _,
+            Some(EffectChild::ColorFilter(c)) => c.inner as _,
+            None => std::ptr::null_mut(),
+        }
+    }
+}
+impl From<Shader> for EffectChild {
+    fn from(value: Shader) -> Self {
+        Self::Shader(value)
+    }
+}
+impl From<ColorFilter> for EffectChild {
+    fn from(value: ColorFilter) -> Self {
+        Self::ColorFilter(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UniformError {
+    UnknownUniform(String),
+    /// tried to set ints on a float uniform or vice versa
+    TypeMismatch {
+        name: String,
+        expected: RuntimeEffectUniformType,
+    },
+    /// number of bytes provided vs the size of the uniform
+    SizeMismatch {
+        name: String,
+        expected: usize,
+        actual: usize,
+    },
+    /// the effect declares a uniform that is not provided by a [SkslUniforms] struct
+    MissingUniform(String),
+    UnknownChild(String),
+    ChildTypeMismatch {
+        name: String,
+        expected: RuntimeEffectChildType,
+    },
+}
+impl Display for UniformError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UniformError::UnknownUniform(name) => write!(f, "no uniform named {name}"),
+            UniformError::TypeMismatch { name, expected } => {
+                write!(f, "uniform {name} has type {expected:?}")
+            }
+            UniformError::SizeMismatch {
+                name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "uniform {name} is {expected} bytes, but {actual} bytes were provided"
+            ),
+            UniformError::MissingUniform(name) => write!(f, "uniform {name} is not provided"),
+            UniformError::UnknownChild(name) => write!(f, "no child named {name}"),
+            UniformError::ChildTypeMismatch { name, expected } => {
+                write!(f, "child {name} has type {expected:?}")
+            }
+        }
+    }
+}
+impl std::error::Error for UniformError {}
+
+/// Collects uniforms and children for a [RuntimeEffect], validating them against the effect's reflection data.
+/// Unset uniforms are zero and unset children are null.
+///
+/// ```rust,ignore
+/// let effect = RuntimeEffect::for_shader(SKSL)?;
+/// let shader = effect
+///     .builder()
+///     .set_floats("radius", &[12.0])?
+///     .set_color("tint", &tint)?
+///     .set_child("image", image_shader)?
+///     .make_shader(None);
+/// ```
+pub struct RuntimeEffectBuilder<'e> {
+    effect: &'e RuntimeEffect,
+    uniforms: Vec<UniformInfo>,
+    children: Vec<ChildInfo>,
+    data: Vec<u8>,
+    inputs: Vec<Option<EffectChild>>,
+}
+impl<'e> RuntimeEffectBuilder<'e> {
+    pub fn new(effect: &'e RuntimeEffect) -> Self {
+        let children = effect.get_children();
+        Self {
+            effect,
+            uniforms: effect.get_uniforms(),
+            data: vec![0; effect.get_uniform_byte_size()],
+            inputs: children.iter().map(|_| None).collect(),
+            children,
+        }
+    }
+    pub fn get_effect(&self) -> &'e RuntimeEffect {
+        self.effect
+    }
+    /// raw uniform data as it will be passed to skia
+    pub fn get_uniform_data(&self) -> &[u8] {
+        &self.data
+    }
+    fn write(&mut self, name: &str, float: bool, bytes: &[u8]) -> Result<&mut Self, UniformError> {
+        let uniform = self
+            .uniforms
+            .iter()
+            .find(|u| u.name == name)
+            .ok_or_else(|| UniformError::UnknownUniform(name.to_string()))?;
+        if uniform.is_float() != float {
+            return Err(UniformError::TypeMismatch {
+                name: name.to_string(),
+                expected: uniform.ty,
+            });
+        }
+        if uniform.size_in_bytes() != bytes.len() {
+            return Err(UniformError::SizeMismatch {
+                name: name.to_string(),
+                expected: uniform.size_in_bytes(),
+                actual: bytes.len(),
+            });
+        }
+        let offset = uniform.offset;
+        self.data[offset..offset + bytes.len()].copy_from_slice(bytes);
+        Ok(self)
+    }
+    /// `values` must contain all components of all elements. eg: 6 floats for `uniform float3 points[2];`
+    pub fn set_floats(&mut self, name: &str, values: &[f32]) -> Result<&mut Self, UniformError> {
+        let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_ne_bytes()).collect();
+        self.write(name, true, &bytes)
+    }
+    /// `values` must contain all components of all elements. eg: 2 ints for `uniform int2 size;`
+    pub fn set_ints(&mut self, name: &str, values: &[i32]) -> Result<&mut Self, UniformError> {
+        let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_ne_bytes()).collect();
+        self.write(name, false, &bytes)
+    }
+    /// for `float4`/`half4` uniforms (usually marked with `layout(color)`)
+    pub fn set_color(&mut self, name: &str, color: &Color4f) -> Result<&mut Self, UniformError> {
+        self.set_floats(name, &[color.fR, color.fG, color.fB, color.fA])
+    }
+    /// for `float3x3` uniforms. sksl matrices are column major.
+    pub fn set_matrix(&mut self, name: &str, matrix: &Matrix) -> Result<&mut Self, UniformError> {
+        self.set_floats(
+            name,
+            &[
+                matrix.scaleX,
+                matrix.skewY,
+                matrix.persp0,
+                matrix.skewX,
+                matrix.scaleY,
+                matrix.persp1,
+                matrix.transX,
+                matrix.transY,
+                matrix.persp2,
+            ],
+        )
+    }
+    /// writes all fields of `uniforms`. See [TypedRuntimeEffect] to validate the struct once, when the effect is created.
+    pub fn set_uniforms<U: SkslUniforms>(
+        &mut self,
+        uniforms: &U,
+    ) -> Result<&mut Self, UniformError> {
+        let mut result = Ok(());
+        uniforms.for_each_uniform(&mut |name, bytes| {
+            if result.is_ok() {
+                let float = self
+                    .uniforms
+                    .iter()
+                    .find(|u| u.name == name)
+                    .map_or(true, |u| u.is_float());
+                result = self.write(name, float, bytes).map(|_| ());
+            }
+        });
+        result.map(|_| self)
+    }
+    /// `child` must match the declared type of the child (`shader` or `colorFilter`).
+    pub fn set_child(
+        &mut self,
+        name: &str,
+        child: impl Into<EffectChild>,
+    ) -> Result<&mut Self, UniformError> {
+        let child = child.into();
+        let (index, info) = self
+            .children
+            .iter()
+            .enumerate()
+            .find(|(_, c)| c.name == name)
+            .ok_or_else(|| UniformError::UnknownChild(name.to_string()))?;
+        if info.ty != child.get_type() {
+            return Err(UniformError::ChildTypeMismatch {
+                name: name.to_string(),
+                expected: info.ty,
+            });
+        }
+        self.inputs[index] = Some(child);
+        Ok(self)
+    }
+    /// None if the effect is not a shader effect.
+    pub fn make_shader(&self, local_matrix: Option<&Matrix>) -> Option<Shader> {
+        self.effect.make_shader(
+            &SkiaData::new_with_copy(&self.data),
+            &self.inputs,
+            local_matrix,
+        )
+    }
+    /// None if the effect is not a color filter effect.
+    pub fn make_color_filter(&self) -> Option<ColorFilter> {
+        self.effect
+            .make_color_filter(&SkiaData::new_with_copy(&self.data), &self.inputs)
+    }
+}
+
+/// A rust type that can be written into a uniform of type [Self::TYPE].
+pub trait SkslUniformValue {
+    const TYPE: RuntimeEffectUniformType;
+    /// appends the value in the layout expected by sksl
+    fn append_bytes(&self, bytes: &mut Vec<u8>);
+}
+macro_rules! sksl_uniform_value {
+    ([$ty: ty; $n: literal], $uniform: ident) => {
+        impl SkslUniformValue for [$ty; $n] {
+            const TYPE: RuntimeEffectUniformType = RuntimeEffectUniformType::$uniform;
+            fn append_bytes(&self, bytes: &mut Vec<u8>) {
+                for v in self {
+                    bytes.extend_from_slice(&v.to_ne_bytes());
+                }
+            }
+        }
+    };
+    ($ty: ty, $uniform: ident) => {
+        impl SkslUniformValue for $ty {
+            const TYPE: RuntimeEffectUniformType = RuntimeEffectUniformType::$uniform;
+            fn append_bytes(&self, bytes: &mut Vec<u8>) {
+                bytes.extend_from_slice(&self.to_ne_bytes());
+            }
+        }
+    };
+}
+sksl_uniform_value!(f32, FLOAT_SK_RUNTIMEEFFECT_UNIFORM_TYPE);
+sksl_uniform_value!([f32; 2], FLOAT2_SK_RUNTIMEEFFECT_UNIFORM_TYPE);
+sksl_uniform_value!([f32; 3], FLOAT3_SK_RUNTIMEEFFECT_UNIFORM_TYPE);
+sksl_uniform_value!([f32; 4], FLOAT4_SK_RUNTIMEEFFECT_UNIFORM_TYPE);
+sksl_uniform_value!(i32, INT_SK_RUNTIMEEFFECT_UNIFORM_TYPE);
+sksl_uniform_value!([i32; 2], INT2_SK_RUNTIMEEFFECT_UNIFORM_TYPE);
+sksl_uniform_value!([i32; 3], INT3_SK_RUNTIMEEFFECT_UNIFORM_TYPE);
+sksl_uniform_value!([i32; 4], INT4_SK_RUNTIMEEFFECT_UNIFORM_TYPE);
+impl SkslUniformValue for Color4f {
+    const TYPE: RuntimeEffectUniformType =
+        RuntimeEffectUniformType::FLOAT4_SK_RUNTIMEEFFECT_UNIFORM_TYPE;
+    fn append_bytes(&self, bytes: &mut Vec<u8>) {
+        [self.fR, self.fG, self.fB, self.fA].append_bytes(bytes)
+    }
+}
+impl SkslUniformValue for Matrix {
+    const TYPE: RuntimeEffectUniformType =
+        RuntimeEffectUniformType::FLOAT3X3_SK_RUNTIMEEFFECT_UNIFORM_TYPE;
+    /// column major
+    fn append_bytes(&self, bytes: &mut Vec<u8>) {
+        for v in [
+            self.scaleX,
+            self.skewY,
+            self.persp0,
+            self.skewX,
+            self.scaleY,
+            self.persp1,
+            self.transX,
+            self.transY,
+            self.persp2,
+        ] {
+            bytes.extend_from_slice(&v.to_ne_bytes());
+        }
+    }
+}
+/// A struct whose fields map to the uniforms of a [RuntimeEffect].
+///
+/// With the `derive` feature, use `#[derive(SkslUniforms)]` instead of implementing this by hand.
+///
+/// ```rust,ignore
+/// #[derive(SkslUniforms)]
+/// struct Ripple {
+///     center: [f32; 2],
+///     #[sksl(name = "t")]
+///     time: f32,
+///     tint: Color4f,
+/// }
+/// let effect = TypedRuntimeEffect::<Ripple>::new(RuntimeEffect::for_shader(SKSL)?)?;
+/// let shader = effect.make_shader(&ripple, None)?;
+/// ```
+pub trait SkslUniforms {
+    /// uniform name and type of each field
+    fn uniform_fields() -> Vec<(&'static str, RuntimeEffectUniformType)>;
+    /// calls `f` with the uniform name and bytes of each field
+    fn for_each_uniform(&self, f: &mut dyn FnMut(&'static str, &[u8]));
+}
+#[cfg(feature = "derive")]
+pub use ckia_derive::SkslUniforms;
+
+impl RuntimeEffect {
+    /// checks that the uniforms of `U` match the uniforms declared by the effect exactly (names, types and sizes).
+    pub fn check_uniforms<U: SkslUniforms>(&self) -> Result<(), UniformError> {
+        let declared = self.get_uniforms();
+        let fields = U::uniform_fields();
+        for (name, ty) in &fields {
+            let uniform = self
+                .get_uniform_from_name(name)
+                .ok_or_else(|| UniformError::UnknownUniform(name.to_string()))?;
+            if uniform.ty != *ty {
+                return Err(UniformError::TypeMismatch {
+                    name: name.to_string(),
+                    expected: uniform.ty,
+                });
+            }
+            if uniform.count != 1 {
+                let single = UniformInfo {
+                    count: 1,
+                    ..uniform.clone()
+                };
+                return Err(UniformError::SizeMismatch {
+                    name: name.to_string(),
+                    expected: uniform.size_in_bytes(),
+                    actual: single.size_in_bytes(),
+                });
+            }
+        }
+        if let Some(missing) = declared
+            .iter()
+            .find(|u| !fields.iter().any(|(name, _)| *name == u.name))
+        {
+            return Err(UniformError::MissingUniform(missing.name.clone()));
+        }
+        Ok(())
+    }
+}
+/// A [RuntimeEffect] whose uniforms were checked against `U` when it was created.
+pub struct TypedRuntimeEffect<U: SkslUniforms> {
+    effect: RuntimeEffect,
+    phantom: std::marker::PhantomData<fn(&U)>,
+}
+impl<U: SkslUniforms> TypedRuntimeEffect<U> {
+    pub fn new(effect: RuntimeEffect) -> Result<Self, UniformError> {
+        effect.check_uniforms::<U>()?;
+        Ok(Self {
+            effect,
+            phantom: std::marker::PhantomData,
+        })
+    }
+    pub fn get_effect(&self) -> &RuntimeEffect {
+        &self.effect
+    }
+    /// builder with all uniforms set. Use it to set children.
+    ///
+    /// The fields are checked again against the effect, so a hand written [SkslUniforms] impl
+    /// whose `for_each_uniform` doesn't match its `uniform_fields` fails here instead of panicking.
+    pub fn builder(&self, uniforms: &U) -> Result<RuntimeEffectBuilder<'_>, UniformError> {
+        let mut builder = self.effect.builder();
+        let mut result = Ok(());
+        uniforms.for_each_uniform(&mut |name, bytes| {
+            if result.is_ok() {
+                result = match self.effect.get_uniform_from_name(name) {
+                    Some(uniform) => builder.write(name, uniform.is_float(), bytes).map(|_| ()),
+                    None => Err(UniformError::UnknownUniform(name.to_string())),
+                };
+            }
+        });
+        result.map(|_| builder)
+    }
+    /// for effects without children. The inner None is returned if skia fails to create the shader.
+    pub fn make_shader(
+        &self,
+        uniforms: &U,
+        local_matrix: Option<&Matrix>,
+    ) -> Result<Option<Shader>, UniformError> {
+        Ok(self.builder(uniforms)?.make_shader(local_matrix))
+    }
+    /// for effects without children. The inner None is returned if skia fails to create the color filter.
+    pub fn make_color_filter(&self, uniforms: &U) -> Result<Option<ColorFilter>, UniformError> {
+        Ok(self.builder(uniforms)?.make_color_filter())
+    }
+}
@@ -1,19 +1,19 @@
 use crate::{bindings::*, SkiaOptPtr};
 
+use std::fmt::Display;
+
 use crate::{
-    color::ColorSpace, filter::ColorFilter, skia_wrapper, BlendMode, Color, Color4f, Matrix, Point,
-    ShaderTileMode,
+    color::ColorSpace, filter::ColorFilter, skia_wrapper, BlendMode, Color, Color4f, ISize, Matrix,
+    Point, ShaderTileMode,
 };
 
 skia_wrapper!(refcnt, Shader, sk_shader_t, sk_shader_unref, sk_shader_ref);
 
 impl Shader {
-    /*
-    pub fn sk_shader_with_local_matrix(
-        shader: *const sk_shader_t,
-        localMatrix: *const sk_matrix_t,
-    ) -> *mut sk_shader_t;
-    */
+    /// `matrix` is applied to the shader's coordinates before its existing local matrix.
+    pub fn with_local_matrix(&self, matrix: &Matrix) -> Self {
+        unsafe { Self::from_owned_ptr(sk_shader_with_local_matrix(self.inner, matrix.as_ptr())) }
+    }
     pub fn with_color_filter(&self, filter: &ColorFilter) -> Self {
         unsafe { Self::from_owned_ptr(sk_shader_with_color_filter(self.inner, filter.inner)) }
     }
@@ -77,63 +77,493 @@ impl Shader {
             ))
         }
     }
-    /*
-    pub fn sk_shader_new_sweep_gradient(
-        center: *const sk_point_t,
-        colors: *const sk_color_t,
-        colorPos: *const f32,
-        colorCount: ::std::os::raw::c_int,
-        tileMode: sk_shader_tilemode_t,
-        startAngle: f32,
-        endAngle: f32,
-        localMatrix: *const sk_matrix_t,
-    ) -> *mut sk_shader_t;
-    pub fn sk_shader_new_sweep_gradient_color4f(
-        center: *const sk_point_t,
-        colors: *const sk_color4f_t,
-        colorspace: *const sk_colorspace_t,
-        colorPos: *const f32,
-        colorCount: ::std::os::raw::c_int,
-        tileMode: sk_shader_tilemode_t,
-        startAngle: f32,
-        endAngle: f32,
-        localMatrix: *const sk_matrix_t,
-    ) -> *mut sk_shader_t;
-    pub fn sk_shader_new_two_point_conical_gradient(
-        start: *const sk_point_t,
-        startRadius: f32,
-        end: *const sk_point_t,
-        endRadius: f32,
-        colors: *const sk_color_t,
-        colorPos: *const f32,
-        colorCount: ::std::os::raw::c_int,
-        tileMode: sk_shader_tilemode_t,
-        localMatrix: *const sk_matrix_t,
-    ) -> *mut sk_shader_t;
-    pub fn sk_shader_new_two_point_conical_gradient_color4f(
-        start: *const sk_point_t,
-        startRadius: f32,
-        end: *const sk_point_t,
-        endRadius: f32,
-        colors: *const sk_color4f_t,
-        colorspace: *const sk_colorspace_t,
-        colorPos: *const f32,
-        colorCount: ::std::os::raw::c_int,
-        tileMode: sk_shader_tilemode_t,
-        localMatrix: *const sk_matrix_t,
-    ) -> *mut sk_shader_t;
-    pub fn sk_shader_new_perlin_noise_fractal_noise(
-        baseFrequencyX: f32,
-        baseFrequencyY: f32,
-        numOctaves: ::std::os::raw::c_int,
+}
+
+/// Error returned by [GradientStops] when a position is invalid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GradientStopsError {
+    /// positions must be in `0.0..=1.0`. NaN is out of range too.
+    PositionOutOfRange(f32),
+    /// positions must not decrease
+    PositionDecreasing { previous: f32, position: f32 },
+    /// the number of positions must match the number of colors
+    LengthMismatch { colors: usize, positions: usize },
+}
+impl Display for GradientStopsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GradientStopsError::PositionOutOfRange(position) => {
+                write!(f, "gradient stop position {position} is not in 0..=1")
+            }
+            GradientStopsError::PositionDecreasing { previous, position } => write!(
+                f,
+                "gradient stop position {position} is smaller than the previous position {previous}"
+            ),
+            GradientStopsError::LengthMismatch { colors, positions } => {
+                write!(f, "{colors} colors but {positions} positions")
+            }
+        }
+    }
+}
+impl std::error::Error for GradientStopsError {}
+
+/// The colors of a gradient and where they are placed along it.
+///
+/// `C` is either [Color] or [Color4f]. Positions are always valid: in `0.0..=1.0` and non-decreasing.
+/// Two stops at the same position create a hard edge.
+///
+/// ```rust,ignore
+/// let mut stops = GradientStops::new();
+/// stops.add(0.0, Color::RED)?.add(0.5, Color::GREEN)?.add(1.0, Color::BLUE)?;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct GradientStops<C> {
+    colors: Vec<C>,
+    /// None if the colors are evenly spaced
+    positions: Option<Vec<f32>>,
+}
+impl<C> Default for GradientStops<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<C> GradientStops<C> {
+    pub fn new() -> Self {
+        Self {
+            colors: vec![],
+            positions: Some(vec![]),
+        }
+    }
+    /// the first color is at 0, the last at 1 and the rest are evenly spaced in between.
+    pub fn evenly_spaced(colors: impl Into<Vec<C>>) -> Self {
+        Self {
+            colors: colors.into(),
+            positions: None,
+        }
+    }
+    pub fn with_positions(
+        colors: impl Into<Vec<C>>,
+        positions: impl Into<Vec<f32>>,
+    ) -> Result<Self, GradientStopsError> {
+        let colors = colors.into();
+        let positions = positions.into();
+        if colors.len() != positions.len() {
+            return Err(GradientStopsError::LengthMismatch {
+                colors: colors.len(),
+                positions: positions.len(),
+            });
+        }
+        let mut previous = 0.0;
+        for &position in &positions {
+            check_position(previous, position)?;
+            previous = position;
+        }
+        Ok(Self {
+            colors,
+            positions: Some(positions),
+        })
+    }
+    /// appends a stop. `position` must be in `0.0..=1.0` and not smaller than the position of the last stop.
+    pub fn add(&mut self, position: f32, color: C) -> Result<&mut Self, GradientStopsError> {
+        let previous = match &self.positions {
+            Some(positions) => positions.last().copied().unwrap_or(0.0),
+            None if self.colors.len() > 1 => 1.0,
+            None => 0.0,
+        };
+        check_position(previous, position)?;
+        let len = self.colors.len();
+        self.positions
+            .get_or_insert_with(|| evenly_spaced_positions(len))
+            .push(position);
+        self.colors.push(color);
+        Ok(self)
+    }
+    pub fn get_colors(&self) -> &[C] {
+        &self.colors
+    }
+    /// positions of the colors. computed if they are evenly spaced.
+    pub fn get_positions(&self) -> Vec<f32> {
+        match &self.positions {
+            Some(positions) => positions.clone(),
+            None => evenly_spaced_positions(self.colors.len()),
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.colors.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+    }
+    fn positions_ptr(&self) -> *const f32 {
+        self.positions
+            .as_ref()
+            .map_or(std::ptr::null(), |p| p.as_ptr())
+    }
+}
+fn evenly_spaced_positions(len: usize) -> Vec<f32> {
+    let last = len.saturating_sub(1).max(1) as f32;
+    (0..len).map(|i| i as f32 / last).collect()
+}
+fn check_position(previous: f32, position: f32) -> Result<(), GradientStopsError> {
+    if !(0.0..=1.0).contains(&position) {
+        return Err(GradientStopsError::PositionOutOfRange(position));
+    }
+    if position < previous {
+        return Err(GradientStopsError::PositionDecreasing { previous, position });
+    }
+    Ok(())
+}
+
+/// Gradients made from [GradientStops] return None if there are fewer than 2 stops or the geometry is invalid
+/// (eg: negative radius). The [Color4f] variants interpret the colors in `colorspace` (sRGB if None).
+impl Shader {
+    pub fn new_linear_gradient_with_stops(
+        points: &[Point; 2],
+        stops: &GradientStops<Color>,
+        tile_mode: ShaderTileMode,
+        local_matrix: Option<&Matrix>,
+    ) -> Option<Self> {
+        if stops.len() < 2 {
+            return None;
+        }
+        unsafe {
+            Self::try_from_owned_ptr(sk_shader_new_linear_gradient(
+                points.as_ptr() as _,
+                stops.colors.as_ptr() as _,
+                stops.positions_ptr(),
+                stops.len() as _,
+                tile_mode,
+                local_matrix.or_null(),
+            ))
+        }
+    }
+    pub fn new_linear_gradient_with_stops_color4f(
+        points: &[Point; 2],
+        stops: &GradientStops<Color4f>,
+        colorspace: Option<&ColorSpace>,
+        tile_mode: ShaderTileMode,
+        local_matrix: Option<&Matrix>,
+    ) -> Option<Self> {
+        if stops.len() < 2 {
+            return None;
+        }
+        unsafe {
+            Self::try_from_owned_ptr(sk_shader_new_linear_gradient_color4f(
+                points.as_ptr() as _,
+                stops.colors.as_ptr() as _,
+                colorspace.or_null(),
+                stops.positions_ptr(),
+                stops.len() as _,
+                tile_mode,
+                local_matrix.or_null(),
+            ))
+        }
+    }
+    pub fn new_radial_gradient_with_stops(
+        center: Point,
+        radius: f32,
+        stops: &GradientStops<Color>,
+        tile_mode: ShaderTileMode,
+        local_matrix: Option<&Matrix>,
+    ) -> Option<Self> {
+        if stops.len() < 2 {
+            return None;
+        }
+        unsafe {
+            Self::try_from_owned_ptr(sk_shader_new_radial_gradient(
+                center.as_ptr(),
+                radius,
+                stops.colors.as_ptr() as _,
+                stops.positions_ptr(),
+                stops.len() as _,
+                tile_mode,
+                local_matrix.or_null(),
+            ))
+        }
+    }
+    pub fn new_radial_gradient_with_stops_color4f(
+        center: Point,
+        radius: f32,
+        stops: &GradientStops<Color4f>,
+        colorspace: Option<&ColorSpace>,
+        tile_mode: ShaderTileMode,
+        local_matrix: Option<&Matrix>,
+    ) -> Option<Self> {
+        if stops.len() < 2 {
+            return None;
+        }
+        unsafe {
+            Self::try_from_owned_ptr(sk_shader_new_radial_gradient_color4f(
+                center.as_ptr(),
+                radius,
+                stops.colors.as_ptr() as _,
+                colorspace.or_null(),
+                stops.positions_ptr(),
+                stops.len() as _,
+                tile_mode,
+                local_matrix.or_null(),
+            ))
+        }
+    }
+    /// an angular (conic) gradient around `center`. Angles are in degrees, clockwise from the positive x axis.
+    /// Use 0 and 360 for a full turn. Outside the angles, `tile_mode` decides the color.
+    pub fn new_sweep_gradient_with_stops(
+        center: Point,
+        stops: &GradientStops<Color>,
+        tile_mode: ShaderTileMode,
+        start_angle: f32,
+        end_angle: f32,
+        local_matrix: Option<&Matrix>,
+    ) -> Option<Self> {
+        if stops.len() < 2 {
+            return None;
+        }
+        unsafe {
+            Self::try_from_owned_ptr(sk_shader_new_sweep_gradient(
+                center.as_ptr(),
+                stops.colors.as_ptr() as _,
+                stops.positions_ptr(),
+                stops.len() as _,
+                tile_mode,
+                start_angle,
+                end_angle,
+                local_matrix.or_null(),
+            ))
+        }
+    }
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_sweep_gradient_with_stops_color4f(
+        center: Point,
+        stops: &GradientStops<Color4f>,
+        colorspace: Option<&ColorSpace>,
+        tile_mode: ShaderTileMode,
+        start_angle: f32,
+        end_angle: f32,
+        local_matrix: Option<&Matrix>,
+    ) -> Option<Self> {
+        if stops.len() < 2 {
+            return None;
+        }
+        unsafe {
+            Self::try_from_owned_ptr(sk_shader_new_sweep_gradient_color4f(
+                center.as_ptr(),
+                stops.colors.as_ptr() as _,
+                colorspace.or_null(),
+                stops.positions_ptr(),
+                stops.len() as _,
+                tile_mode,
+                start_angle,
+                end_angle,
+                local_matrix.or_null(),
+            ))
+        }
+    }
+    /// interpolates between the circle at `start` with `start_radius` and the circle at `end` with `end_radius`.
+    pub fn new_two_point_conical_gradient_with_stops(
+        start: Point,
+        start_radius: f32,
+        end: Point,
+        end_radius: f32,
+        stops: &GradientStops<Color>,
+        tile_mode: ShaderTileMode,
+        local_matrix: Option<&Matrix>,
+    ) -> Option<Self> {
+        if stops.len() < 2 {
+            return None;
+        }
+        unsafe {
+            Self::try_from_owned_ptr(sk_shader_new_two_point_conical_gradient(
+                start.as_ptr(),
+                start_radius,
+                end.as_ptr(),
+                end_radius,
+                stops.colors.as_ptr() as _,
+                stops.positions_ptr(),
+                stops.len() as _,
+                tile_mode,
+                local_matrix.or_null(),
+            ))
+        }
+    }
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_two_point_conical_gradient_with_stops_color4f(
+        start: Point,
+        start_radius: f32,
+        end: Point,
+        end_radius: f32,
+        stops: &GradientStops<Color4f>,
+        colorspace: Option<&ColorSpace>,
+        tile_mode: ShaderTileMode,
+        local_matrix: Option<&Matrix>,
+    ) -> Option<Self> {
+        if stops.len() < 2 {
+            return None;
+        }
+        unsafe {
+            Self::try_from_owned_ptr(sk_shader_new_two_point_conical_gradient_color4f(
+                start.as_ptr(),
+                start_radius,
+                end.as_ptr(),
+                end_radius,
+                stops.colors.as_ptr() as _,
+                colorspace.or_null(),
+                stops.positions_ptr(),
+                stops.len() as _,
+                tile_mode,
+                local_matrix.or_null(),
+            ))
+        }
+    }
+    /// Perlin noise as in the svg `feTurbulence` filter with `type="fractalNoise"`.
+    /// Frequencies must be non-negative and `num_octaves` below 256, otherwise returns None.
+    /// With `tile_size`, the noise is stitched so that it tiles seamlessly.
+    pub fn new_perlin_noise_fractal_noise(
+        base_frequency_x: f32,
+        base_frequency_y: f32,
+        num_octaves: i32,
         seed: f32,
-        tileSize: *const sk_isize_t,
-    ) -> *mut sk_shader_t;
-    pub fn sk_shader_new_perlin_noise_turbulence(
-        baseFrequencyX: f32,
-        baseFrequencyY: f32,
-        numOctaves: ::std::os::raw::c_int,
+        tile_size: Option<&ISize>,
+    ) -> Option<Self> {
+        unsafe {
+            Self::try_from_owned_ptr(sk_shader_new_perlin_noise_fractal_noise(
+                base_frequency_x,
+                base_frequency_y,
+                num_octaves,
+                seed,
+                tile_size.or_null(),
+            ))
+        }
+    }
+    /// same as [Self::new_perlin_noise_fractal_noise], but with `type="turbulence"`.
+    pub fn new_perlin_noise_turbulence(
+        base_frequency_x: f32,
+        base_frequency_y: f32,
+        num_octaves: i32,
         seed: f32,
-        tileSize: *const sk_isize_t,
-    ) -> *mut sk_shader_t; */
+        tile_size: Option<&ISize>,
+    ) -> Option<Self> {
+        unsafe {
+            Self::try_from_owned_ptr(sk_shader_new_perlin_noise_turbulence(
+                base_frequency_x,
+                base_frequency_y,
+                num_octaves,
+                seed,
+                tile_size.or_null(),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{GradientStops, GradientStopsError, Shader};
+    use crate::{Color, Point, ShaderTileMode};
+
+    #[test]
+    pub fn test_gradient_stops_with_positions() {
+        let colors = [Color::RED, Color::GREEN, Color::BLUE];
+        let stops = GradientStops::with_positions(colors, [0.0, 0.5, 1.0]).unwrap();
+        assert_eq!(stops.get_positions(), vec![0.0, 0.5, 1.0]);
+        // a hard edge
+        assert!(GradientStops::with_positions(colors, [0.0, 0.5, 0.5]).is_ok());
+        assert_eq!(
+            GradientStops::with_positions(colors, [0.0, 1.0]),
+            Err(GradientStopsError::LengthMismatch {
+                colors: 3,
+                positions: 2
+            })
+        );
+        assert_eq!(
+            GradientStops::with_positions(colors, [0.0, 0.6, 0.4]),
+            Err(GradientStopsError::PositionDecreasing {
+                previous: 0.6,
+                position: 0.4
+            })
+        );
+        for position in [-0.1, 1.1, f32::INFINITY] {
+            assert_eq!(
+                GradientStops::with_positions(colors, [0.0, position, 1.0]),
+                Err(GradientStopsError::PositionOutOfRange(position))
+            );
+        }
+        assert!(matches!(
+            GradientStops::with_positions(colors, [0.0, f32::NAN, 1.0]),
+            Err(GradientStopsError::PositionOutOfRange(p)) if p.is_nan()
+        ));
+    }
+
+    #[test]
+    pub fn test_gradient_stops_add() {
+        let mut stops = GradientStops::new();
+        stops
+            .add(0.0, Color::RED)
+            .unwrap()
+            .add(0.25, Color::GREEN)
+            .unwrap();
+        assert_eq!(
+            stops.add(0.2, Color::BLUE).unwrap_err(),
+            GradientStopsError::PositionDecreasing {
+                previous: 0.25,
+                position: 0.2
+            }
+        );
+        assert_eq!(
+            stops.add(2.0, Color::BLUE).unwrap_err(),
+            GradientStopsError::PositionOutOfRange(2.0)
+        );
+        // failed adds leave the stops untouched
+        assert_eq!(stops.len(), 2);
+        stops.add(1.0, Color::BLUE).unwrap();
+        assert_eq!(stops.get_colors(), &[Color::RED, Color::GREEN, Color::BLUE]);
+        assert_eq!(stops.get_positions(), vec![0.0, 0.25, 1.0]);
+    }
+
+    #[test]
+    pub fn test_gradient_stops_evenly_spaced() {
+        assert_eq!(
+            GradientStops::<Color>::evenly_spaced([]).get_positions(),
+            Vec::<f32>::new()
+        );
+        assert_eq!(
+            GradientStops::evenly_spaced([Color::RED]).get_positions(),
+            vec![0.0]
+        );
+        let mut stops = GradientStops::evenly_spaced([Color::RED, Color::GREEN, Color::BLUE]);
+        assert_eq!(stops.get_positions(), vec![0.0, 0.5, 1.0]);
+        // the last evenly spaced color is at 1
+        assert!(stops.add(0.5, Color::RED).is_err());
+        stops.add(1.0, Color::RED).unwrap();
+        assert_eq!(stops.get_positions(), vec![0.0, 0.5, 1.0, 1.0]);
+        // a single evenly spaced color is at 0
+        let mut stops = GradientStops::evenly_spaced([Color::RED]);
+        stops.add(0.5, Color::BLUE).unwrap();
+        assert_eq!(stops.get_positions(), vec![0.0, 0.5]);
+    }
+
+    #[test]
+    pub fn test_gradients_need_two_stops() {
+        let mut stops = GradientStops::new();
+        stops.add(0.0, Color::RED).unwrap();
+        let points = [Point::new(0.0, 0.0), Point::new(10.0, 0.0)];
+        let clamp = ShaderTileMode::CLAMP_SK_SHADER_TILEMODE;
+        assert!(Shader::new_linear_gradient_with_stops(&points, &stops, clamp, None).is_none());
+        assert!(
+            Shader::new_radial_gradient_with_stops(points[0], 5.0, &stops, clamp, None).is_none()
+        );
+        assert!(
+            Shader::new_sweep_gradient_with_stops(points[0], &stops, clamp, 0.0, 360.0, None)
+                .is_none()
+        );
+        assert!(Shader::new_two_point_conical_gradient_with_stops(
+            points[0], 1.0, points[1], 5.0, &stops, clamp, None
+        )
+        .is_none());
+        assert!(Shader::new_linear_gradient_with_stops(
+            &points,
+            &GradientStops::new(),
+            clamp,
+            None
+        )
+        .is_none());
+    }
 }
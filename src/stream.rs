@@ -48,6 +48,9 @@ unsafe impl Stream for StreamAsset {
     fn borrow_stream_mut_ptr(&mut self) -> *mut sk_stream_t {
         self.inner as _
     }
+    unsafe fn into_owned_stream_ptr(self) -> *mut sk_stream_t {
+        self.into_owned_ptr() as _
+    }
 }
 crate::skia_wrapper!(
     unique,
@@ -68,6 +71,9 @@ unsafe impl Stream for FileStream {
     fn borrow_stream_mut_ptr(&mut self) -> *mut sk_stream_t {
         self.inner as _
     }
+    unsafe fn into_owned_stream_ptr(self) -> *mut sk_stream_t {
+        self.into_owned_ptr() as _
+    }
 }
 crate::skia_wrapper!(
     unique,
@@ -183,6 +189,9 @@ unsafe impl<'a> Stream for MemoryStream<'a> {
     fn borrow_stream_mut_ptr(&mut self) -> *mut sk_stream_t {
         self.inner as _
     }
+    unsafe fn into_owned_stream_ptr(self) -> *mut sk_stream_t {
+        self.into_owned_ptr() as _
+    }
 }
 /// # Safety
 /// Do NOT implement this outside of this crate. All of the default impl methods in this crate basically take a mut pointer and send it across FFI boundary where methods are dispatched using vtables
@@ -261,6 +270,13 @@ pub unsafe trait WStream {
 /// Do NOT impl this out of crate. All of the default implemented methods take raw pointer, send it across FFI boundary, so that the relevant method can be dispatched using vtables.
 pub unsafe trait Stream {
     fn borrow_stream_mut_ptr(&mut self) -> *mut sk_stream_t;
+    /// consumes the stream and returns a ptr that has ownership.
+    /// Used by functions which adopt the stream (eg: [crate::codec::Codec::new_from_stream]).
+    /// # Safety
+    /// caller needs to destroy the stream after being done with it (or hand it to skia which will destroy it).
+    unsafe fn into_owned_stream_ptr(self) -> *mut sk_stream_t
+    where
+        Self: Sized;
     fn read(&mut self, buffer: &mut [u8]) -> usize {
         let stream = self.borrow_stream_mut_ptr();
         unsafe { sk_stream_read(stream, buffer.as_mut_ptr() as _, buffer.len()) }
@@ -6,8 +6,10 @@ use crate::{
     canvas::Canvas,
     color::ColorSpace,
     gr_context::{BackendRenderTarget, BackendTexture, GrRecordingContext},
+    image::Image,
     paint::Paint,
-    ColorType, ImageInfo, PixelGeometry, SurfaceOrigin,
+    pixmap::{PixMap, ReadOnlyPixMap},
+    ColorType, IRect, ImageInfo, PixelGeometry, SurfaceOrigin,
 };
 /// A surface backed by a GPU texture
 pub struct TextureSurface {
@@ -52,6 +54,28 @@ impl DerefMut for TextureSurface {
         &mut self.surface
     }
 }
+/// A surface that draws into a pixel buffer owned by the caller. See [Surface::new_raster_direct].
+pub struct RasterDirectSurface<'a> {
+    surface: Surface,
+    phantom: PhantomData<&'a mut [u8]>,
+}
+impl RasterDirectSurface<'_> {
+    /// The canvas can't outlive this borrow, so it can't outlive the pixels either.
+    pub fn get_canvas(&mut self) -> impl AsMut<Canvas> + '_ {
+        self.surface.get_canvas()
+    }
+    /// Captures the current contents of the surface as an image. The pixels are copied.
+    pub fn new_image_snapshot(&mut self) -> Image {
+        self.surface.new_image_snapshot()
+    }
+    /// see [Surface::peek_pixels]
+    pub fn peek_pixels<'b, T>(
+        &mut self,
+        pixmap: PixMap<'b, T>,
+    ) -> Result<ReadOnlyPixMap<'_, Surface>, PixMap<'b, T>> {
+        self.surface.peek_pixels(pixmap)
+    }
+}
 crate::skia_wrapper!(refcnt, Surface, sk_surface_t, sk_surface_unref);
 
 impl Surface {
@@ -67,15 +91,40 @@ impl Surface {
             ))
         }
     }
-    /*
-    pub fn sk_surface_new_raster_direct(
-        arg1: *const sk_imageinfo_t,
-        pixels: *mut ::std::os::raw::c_void,
-        rowBytes: usize,
-        releaseProc: sk_surface_raster_release_proc,
-        context: *mut ::std::os::raw::c_void,
-        props: *const sk_surfaceprops_t,
-    ) -> *mut sk_surface_t; */
+    /// draws directly into `pixels`, which is laid out as described by `info` and `row_bytes`.
+    /// Image snapshots copy the pixels, so they can outlive the buffer.
+    ///
+    /// Returns None if `row_bytes` is too small for the width, if `pixels` is shorter than `height * row_bytes`
+    /// (even though the last row doesn't need padding) or if skia doesn't support rendering into `info`
+    /// (eg: unknown color type or more than 2 GiB of pixels).
+    pub fn new_raster_direct<'a>(
+        info: &ImageInfo,
+        pixels: &'a mut [u8],
+        row_bytes: usize,
+        props: &SurfaceProps,
+    ) -> Option<RasterDirectSurface<'a>> {
+        let height = info.get_height().max(0) as usize;
+        if row_bytes < info.min_row_bytes()
+            || height
+                .checked_mul(row_bytes)
+                .map_or(true, |size| pixels.len() < size)
+        {
+            return None;
+        }
+        unsafe {
+            Some(RasterDirectSurface {
+                surface: Self::try_from_owned_ptr(sk_surface_new_raster_direct(
+                    info.as_ptr(),
+                    pixels.as_mut_ptr() as _,
+                    row_bytes,
+                    None,
+                    std::ptr::null_mut(),
+                    props.as_ptr(),
+                ))?,
+                phantom: PhantomData,
+            })
+        }
+    }
 
     pub unsafe fn new_backend_render_target(
         context: &mut GrRecordingContext,
@@ -153,21 +202,44 @@ impl Surface {
         }
     }
 
-    /*
-
-
-      pub fn sk_surface_new_image_snapshot(arg1: *mut sk_surface_t) -> *mut sk_image_t;
-      pub fn sk_surface_new_image_snapshot_with_crop(
-          surface: *mut sk_surface_t,
-          bounds: *const sk_irect_t,
-      ) -> *mut sk_image_t;
-    */
+    /// Captures the current contents of the surface as an image.
+    pub fn new_image_snapshot(&mut self) -> Image {
+        unsafe { Image::from_owned_ptr(sk_surface_new_image_snapshot(self.as_ptr_mut())) }
+    }
+    /// returns None if `bounds` doesn't intersect the surface
+    pub fn new_image_snapshot_with_crop(&mut self, bounds: &IRect) -> Option<Image> {
+        unsafe {
+            Image::try_from_owned_ptr(sk_surface_new_image_snapshot_with_crop(
+                self.as_ptr_mut(),
+                bounds.as_ptr(),
+            ))
+        }
+    }
     /// Draws this surface to the target `canvas` at position `x` and `y`
     pub fn draw_to(&mut self, canvas: &mut Canvas, x: f32, y: f32, paint: &Paint) {
         unsafe { sk_surface_draw(self.as_ptr_mut(), canvas.as_ptr_mut(), x, y, paint.as_ptr()) };
     }
+    /// If we succeed, we will return the pixmap with lifetime attached to this surface.
+    /// Fails for surfaces without cpu accessible pixels (eg: gpu surfaces), and returns the pixmap untouched.
+    ///
+    /// The pixmap is read-only, as writing to it would bypass the surface's copy-on-write
+    /// and change images that were snapshotted earlier. Draw with [Self::get_canvas] instead.
+    pub fn peek_pixels<'b, T>(
+        &mut self,
+        pixmap: PixMap<'b, T>,
+    ) -> Result<ReadOnlyPixMap<'_, Self>, PixMap<'b, T>> {
+        unsafe {
+            if sk_surface_peek_pixels(self.as_ptr_mut(), pixmap.inner) {
+                Ok(ReadOnlyPixMap::new(std::mem::transmute::<
+                    PixMap<'b, T>,
+                    PixMap<'_, Self>,
+                >(pixmap)))
+            } else {
+                Err(pixmap)
+            }
+        }
+    }
     /*
-       pub fn sk_surface_peek_pixels(surface: *mut sk_surface_t, pixmap: *mut sk_pixmap_t) -> bool;
        pub fn sk_surface_read_pixels(
            surface: *mut sk_surface_t,
            dstInfo: *mut sk_imageinfo_t,
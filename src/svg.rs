@@ -0,0 +1,86 @@
+use std::{
+    borrow::{Borrow, BorrowMut},
+    marker::PhantomData,
+};
+
+use crate::bindings::*;
+use crate::{
+    canvas::Canvas,
+    paint::Paint,
+    picture::Picture,
+    stream::{DynamicMemoryStream, WStream},
+    Matrix, Rect,
+};
+
+/// A canvas that converts draw calls into svg elements and writes them into a [WStream].
+///
+/// The svg document is complete only after the canvas is dropped (or [Self::finish] is called).
+///
+/// **NOTE**: The C api always uses the default flags (text is kept as `<text>` elements and the output is pretty-printed).
+/// Converting text to paths or disabling pretty-printing is not supported.
+pub struct SvgCanvas<'s> {
+    // not a Canvas, so that it can't be swapped out and outlive the stream
+    canvas: *mut sk_canvas_t,
+    phantom: PhantomData<&'s mut ()>,
+}
+impl<'s> SvgCanvas<'s> {
+    /// only the width and height of `bounds` are used as the size of the svg.
+    /// None if skia could not create the canvas.
+    pub fn new(bounds: &Rect, stream: &'s mut impl WStream) -> Option<Self> {
+        unsafe {
+            let canvas =
+                sk_svgcanvas_create_with_stream(bounds.as_ptr(), stream.borrow_wstream_mut_ptr());
+            if canvas.is_null() {
+                return None;
+            }
+            Some(Self {
+                canvas,
+                phantom: PhantomData,
+            })
+        }
+    }
+    /// writes the closing tags. Same as dropping the canvas.
+    pub fn finish(self) {}
+}
+impl Drop for SvgCanvas<'_> {
+    fn drop(&mut self) {
+        unsafe { sk_canvas_destroy(self.canvas) }
+    }
+}
+impl AsMut<Canvas> for SvgCanvas<'_> {
+    fn as_mut(&mut self) -> &mut Canvas {
+        unsafe { std::mem::transmute::<&mut *mut sk_canvas_t, &mut Canvas>(&mut self.canvas) }
+    }
+}
+impl Borrow<Canvas> for SvgCanvas<'_> {
+    fn borrow(&self) -> &Canvas {
+        unsafe { std::mem::transmute::<&*mut sk_canvas_t, &Canvas>(&self.canvas) }
+    }
+}
+impl BorrowMut<Canvas> for SvgCanvas<'_> {
+    fn borrow_mut(&mut self) -> &mut Canvas {
+        self.as_mut()
+    }
+}
+
+impl Picture {
+    /// draws the picture into an svg document of the size of its cull rect.
+    /// The top left of the cull rect becomes the origin of the svg.
+    /// None if the svg canvas could not be created.
+    pub fn to_svg_string(&mut self) -> Option<String> {
+        let cull = self.get_cull_rect();
+        let mut stream = DynamicMemoryStream::default();
+        {
+            let mut canvas = SvgCanvas::new(&cull, &mut stream)?;
+            let matrix = Matrix {
+                transX: -cull.left,
+                transY: -cull.top,
+                ..Matrix::IDENTITY
+            };
+            canvas
+                .as_mut()
+                .draw_picture(self, &matrix, &Paint::default());
+        }
+        Some(String::from_utf8_lossy(stream.detach_as_data().get_bytes()).into_owned())
+    }
+}
@@ -0,0 +1,503 @@
+use std::ops::Range;
+
+use crate::{
+    canvas::Canvas,
+    document::PdfDocument,
+    paragraph::{Paragraph, ParagraphBuider},
+    ClipOp, Rect, RectHeightStyle, RectWidthStyle, TextBox, TextDirection,
+};
+
+/// tolerance used when comparing line positions
+const EPSILON: f32 = 0.01;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlowOptions {
+    /// minimum number of lines of a paragraph that must stay at the bottom of a page before a break.
+    /// If fewer lines fit, the paragraph starts on the next page instead.
+    pub orphans: usize,
+    /// minimum number of lines of a paragraph that must be carried over to the top of the next page.
+    /// If fewer lines would be carried over, lines are pulled from the previous page.
+    pub widows: usize,
+    /// vertical space between consecutive paragraphs on the same page.
+    pub paragraph_spacing: f32,
+}
+impl Default for FlowOptions {
+    fn default() -> Self {
+        Self {
+            orphans: 2,
+            widows: 2,
+            paragraph_spacing: 0.0,
+        }
+    }
+}
+/// A set of consecutive lines of a paragraph placed on a page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlowSlice {
+    /// index into the paragraphs that were laid out
+    pub paragraph: usize,
+    pub lines: Range<usize>,
+    /// utf-8 range of the text within these lines
+    pub text_range: Range<usize>,
+    /// vertical range of the lines in paragraph coordinates
+    pub source: Range<f32>,
+    /// offset from the top of the content rect where the slice is painted
+    pub y: f32,
+}
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FlowPage {
+    pub slices: Vec<FlowSlice>,
+}
+/// A paragraph and the text it was built from.
+///
+/// skia reports the text of each line as a utf-8 range, but measures boxes over utf-16 ranges.
+/// So, the flow needs the text to measure lines of non-ascii text.
+pub struct FlowParagraph {
+    pub paragraph: Paragraph,
+    pub text: String,
+}
+impl FlowParagraph {
+    /// copies the text of `builder` and builds the paragraph.
+    pub fn new(builder: &mut ParagraphBuider) -> Self {
+        let text = builder.get_text().to_owned();
+        Self {
+            paragraph: builder.build(),
+            text,
+        }
+    }
+}
+
+/// Splits paragraphs at line boundaries to flow them over multiple pages.
+///
+/// ```rust,ignore
+/// let mut paragraphs: Vec<FlowParagraph> = builders.iter_mut().map(FlowParagraph::new).collect();
+/// let flow = TextFlow::new(&mut paragraphs, &content, &FlowOptions::default());
+/// flow.paint_into_document(&mut paragraphs, &mut document, 595.0, 842.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TextFlow {
+    content: Rect,
+    pages: Vec<FlowPage>,
+}
+impl TextFlow {
+    /// lays out each paragraph to the width of `content` and distributes their lines over pages of `content` height.
+    /// Lines taller than the content rect get a page of their own and are clipped.
+    pub fn new(paragraphs: &mut [FlowParagraph], content: &Rect, options: &FlowOptions) -> Self {
+        let lines: Vec<Vec<(f32, f32)>> = paragraphs
+            .iter_mut()
+            .map(|p| {
+                p.paragraph.layout(content.right - content.left);
+                line_extents(&mut p.paragraph, &p.text)
+            })
+            .collect();
+        let pages = paginate(&lines, content.bottom - content.top, options)
+            .into_iter()
+            .map(|placements| FlowPage {
+                slices: placements
+                    .into_iter()
+                    .map(|placement| {
+                        let paragraph = &mut paragraphs[placement.paragraph].paragraph;
+                        let lines = placement.lines;
+                        let text_start =
+                            paragraph.get_actual_text_range(lines.start as i32, true).0;
+                        let text_end = paragraph
+                            .get_actual_text_range(lines.end as i32 - 1, true)
+                            .1;
+                        FlowSlice {
+                            paragraph: placement.paragraph,
+                            lines,
+                            text_range: text_start..text_end,
+                            source: placement.source,
+                            y: placement.y,
+                        }
+                    })
+                    .collect(),
+            })
+            .collect();
+        Self {
+            content: *content,
+            pages,
+        }
+    }
+    pub fn get_content_rect(&self) -> &Rect {
+        &self.content
+    }
+    pub fn get_pages(&self) -> &[FlowPage] {
+        &self.pages
+    }
+    pub fn get_page_count(&self) -> usize {
+        self.pages.len()
+    }
+    /// paints the slices of page `index`. `paragraphs` must be the same paragraphs that were used to create the flow.
+    pub fn paint_page(&self, paragraphs: &mut [FlowParagraph], index: usize, canvas: &mut Canvas) {
+        let Some(page) = self.pages.get(index) else {
+            return;
+        };
+        let content = &self.content;
+        let width = content.right - content.left;
+        for slice in &page.slices {
+            let y = content.top + slice.y;
+            canvas.save();
+            // only clip vertically, as glyphs (eg: italics) may overhang the content rect horizontally
+            canvas.clip_rect_with_operation(
+                &Rect::new(
+                    content.left - width,
+                    y,
+                    content.right + width,
+                    y + slice.source.end - slice.source.start,
+                ),
+                ClipOp::INTERSECT_SK_CLIPOP,
+                false,
+            );
+            paragraphs[slice.paragraph].paragraph.paint(
+                canvas,
+                content.left,
+                y - slice.source.start,
+            );
+            canvas.restore();
+        }
+    }
+    /// calls `new_page` with the index of each page and a painter.
+    /// `new_page` is expected to create the page's canvas and call the painter with it.
+    ///
+    /// ```rust,ignore
+    /// flow.paint_pages(&mut paragraphs, |_, paint| {
+    ///     let mut surface = Surface::new_raster(&info, 0, &props);
+    ///     paint(surface.get_canvas().as_mut());
+    ///     images.push(surface.new_image_snapshot());
+    /// });
+    /// ```
+    pub fn paint_pages(
+        &self,
+        paragraphs: &mut [FlowParagraph],
+        mut new_page: impl FnMut(usize, &mut dyn FnMut(&mut Canvas)),
+    ) {
+        for index in 0..self.pages.len() {
+            new_page(index, &mut |canvas| {
+                self.paint_page(paragraphs, index, canvas)
+            });
+        }
+    }
+    /// begins a page of `width` x `height` in `document` for each page of the flow and paints it.
+    pub fn paint_into_document(
+        &self,
+        paragraphs: &mut [FlowParagraph],
+        document: &mut PdfDocument,
+        width: f32,
+        height: f32,
+    ) {
+        for index in 0..self.pages.len() {
+            let mut page = document.begin_page(width, height, None);
+            self.paint_page(paragraphs, index, page.as_mut());
+        }
+    }
+}
+
+/// lines of a paragraph placed on a page, before their text range is looked up.
+#[derive(Debug, Clone, PartialEq)]
+struct Placement {
+    paragraph: usize,
+    lines: Range<usize>,
+    source: Range<f32>,
+    y: f32,
+}
+/// distributes the lines (top, bottom) of each paragraph over pages of `page_height`.
+fn paginate(
+    paragraphs: &[Vec<(f32, f32)>],
+    page_height: f32,
+    options: &FlowOptions,
+) -> Vec<Vec<Placement>> {
+    let mut pages: Vec<Vec<Placement>> = vec![vec![]];
+    let mut cursor = 0.0f32;
+    for (index, lines) in paragraphs.iter().enumerate() {
+        let count = lines.len();
+        if cursor > 0.0 {
+            cursor += options.paragraph_spacing;
+        }
+        let mut start = 0;
+        while start < count {
+            let top = lines[start].0;
+            let available = page_height - cursor;
+            let mut end = start;
+            while end < count && lines[end].1 - top <= available + EPSILON {
+                end += 1;
+            }
+            let page_empty = pages.last().map_or(true, |p| p.is_empty());
+            if end < count {
+                if start == 0 && end < options.orphans.min(count) && !page_empty {
+                    // orphan. move the start of the paragraph to the next page
+                    end = start;
+                } else if count - end < options.widows {
+                    // widow. pull lines to the next page if we can keep enough lines here
+                    let pulled = end.saturating_sub(options.widows - (count - end));
+                    let min_kept = if start == 0 {
+                        options.orphans.max(1)
+                    } else {
+                        1
+                    };
+                    if pulled >= start + min_kept {
+                        end = pulled;
+                    } else if start == 0 && !page_empty {
+                        end = start;
+                    }
+                }
+                if end == start && page_empty {
+                    // the line is taller than the page. place it anyway, so that we make progress.
+                    end = start + 1;
+                }
+            }
+            if end > start {
+                let bottom = lines[end - 1].1;
+                if let Some(page) = pages.last_mut() {
+                    page.push(Placement {
+                        paragraph: index,
+                        lines: start..end,
+                        source: top..bottom,
+                        y: cursor,
+                    });
+                }
+                cursor += bottom - top;
+            }
+            if end < count {
+                pages.push(vec![]);
+                cursor = 0.0;
+            }
+            start = end;
+        }
+    }
+    pages
+}
+
+/// (top, bottom) of each line in paragraph coordinates. The paragraph must be laid out.
+/// `text` is the text the paragraph was built from.
+fn line_extents(paragraph: &mut Paragraph, text: &str) -> Vec<(f32, f32)> {
+    let count = paragraph.line_number();
+    let mut lines: Vec<Option<(f32, f32)>> = Vec::with_capacity(count);
+    for line in 0..count {
+        let (start, end) = paragraph.get_actual_text_range(line as i32, true);
+        // empty lines have an empty range, which may start on the next line.
+        if start >= end || paragraph.get_line_number_at(start) != line as i32 {
+            lines.push(None);
+            continue;
+        }
+        let Some(range) = utf16_range(text, start..end) else {
+            lines.push(None);
+            continue;
+        };
+        let boxes = text_boxes(paragraph, range);
+        // with max height style, boxes span the whole height of their line.
+        lines.push(boxes.iter().fold(None, |extent, b| {
+            let (top, bottom) = extent.unwrap_or((b.rect.top, b.rect.bottom));
+            Some((top.min(b.rect.top), bottom.max(b.rect.bottom)))
+        }));
+    }
+    // lines without boxes fill the space between their neighbours.
+    let height = paragraph.get_height();
+    let mut extents = Vec::with_capacity(count);
+    let mut previous_bottom = 0.0f32;
+    for (i, line) in lines.iter().enumerate() {
+        let extent = line.unwrap_or_else(|| {
+            let next_top = lines[i + 1..]
+                .iter()
+                .flatten()
+                .map(|l| l.0)
+                .next()
+                .unwrap_or(height);
+            let empty = lines[i..].iter().take_while(|l| l.is_none()).count();
+            let h = (next_top - previous_bottom).max(0.0) / empty as f32;
+            (previous_bottom, previous_bottom + h)
+        });
+        previous_bottom = extent.1;
+        extents.push(extent);
+    }
+    extents
+}
+
+/// converts a utf-8 range of `text` into the utf-16 range that `get_rects_for_range` takes.
+/// None if the range is not at char boundaries of `text`.
+fn utf16_range(text: &str, range: Range<usize>) -> Option<Range<usize>> {
+    let start = text.get(..range.start)?.encode_utf16().count();
+    let len = text.get(range)?.encode_utf16().count();
+    Some(start..start + len)
+}
+
+fn text_boxes(paragraph: &mut Paragraph, range: Range<usize>) -> Vec<TextBox> {
+    let query = |paragraph: &mut Paragraph, boxes: Option<&mut Vec<TextBox>>| unsafe {
+        paragraph.get_rects_for_range(
+            range.start as u32,
+            range.end as u32,
+            RectHeightStyle::MAX_RECT_HEIGHT_STYLE,
+            RectWidthStyle::TIGHT_RECT_WIDTH_STYLE,
+            boxes,
+        )
+    };
+    let needed = query(paragraph, None);
+    let mut boxes = vec![
+        TextBox {
+            rect: Rect::ZERO,
+            direction: TextDirection::LTR_TEXT_DIRECTION,
+        };
+        needed
+    ];
+    let filled = query(paragraph, Some(&mut boxes)).min(needed);
+    boxes.truncate(filled);
+    boxes
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        data::SkiaData,
+        paragraph::{FontCollection, ParagraphBuider, ParagraphStyle},
+        typeface::FontMgr,
+        Rect,
+    };
+
+    use super::{
+        line_extents, paginate, utf16_range, FlowOptions, FlowParagraph, TextFlow, EPSILON,
+    };
+
+    /// `counts` lines of height 10 for each paragraph
+    fn lines(counts: &[usize]) -> Vec<Vec<(f32, f32)>> {
+        counts
+            .iter()
+            .map(|&n| {
+                (0..n)
+                    .map(|i| (i as f32 * 10.0, (i + 1) as f32 * 10.0))
+                    .collect()
+            })
+            .collect()
+    }
+    /// (paragraph, lines) of each page
+    fn layout(
+        paragraphs: &[Vec<(f32, f32)>],
+        page_height: f32,
+        options: &FlowOptions,
+    ) -> Vec<Vec<(usize, std::ops::Range<usize>)>> {
+        paginate(paragraphs, page_height, options)
+            .into_iter()
+            .map(|page| page.into_iter().map(|p| (p.paragraph, p.lines)).collect())
+            .collect()
+    }
+
+    #[test]
+    pub fn test_utf16_range() {
+        // é is 2 bytes, 世 and 界 are 3 bytes and 🎉 is 4 bytes (a surrogate pair in utf-16)
+        let text = "héllo 世界 🎉";
+        assert_eq!(utf16_range(text, 0..7), Some(0..6));
+        assert_eq!(utf16_range(text, 7..13), Some(6..8));
+        assert_eq!(utf16_range(text, 14..18), Some(9..11));
+        assert_eq!(utf16_range(text, 0..text.len()), Some(0..11));
+        assert_eq!(utf16_range("plain ascii", 3..8), Some(3..8));
+        // not a char boundary
+        assert_eq!(utf16_range(text, 8..13), None);
+        assert_eq!(utf16_range(text, 0..30), None);
+    }
+
+    #[test]
+    pub fn test_paginate_fits_and_breaks() {
+        let no_rules = FlowOptions {
+            orphans: 1,
+            widows: 1,
+            paragraph_spacing: 0.0,
+        };
+        assert_eq!(
+            layout(&lines(&[3, 3]), 100.0, &no_rules),
+            vec![vec![(0, 0..3), (1, 0..3)]]
+        );
+        assert_eq!(
+            layout(&lines(&[5]), 30.0, &no_rules),
+            vec![vec![(0, 0..3)], vec![(0, 3..5)]]
+        );
+        // a line taller than the page gets a page of its own
+        let tall = vec![vec![(0.0, 10.0), (10.0, 60.0), (60.0, 70.0)]];
+        assert_eq!(
+            layout(&tall, 30.0, &no_rules),
+            vec![vec![(0, 0..1)], vec![(0, 1..2)], vec![(0, 2..3)]]
+        );
+    }
+
+    #[test]
+    pub fn test_paginate_widows() {
+        let options = FlowOptions::default();
+        // 3 lines fit, but that would leave a single line on the next page
+        assert_eq!(
+            layout(&lines(&[4]), 30.0, &options),
+            vec![vec![(0, 0..2)], vec![(0, 2..4)]]
+        );
+        // pulling a line would leave a single line on the first page, so the break stays
+        assert_eq!(
+            layout(&lines(&[3]), 20.0, &options),
+            vec![vec![(0, 0..2)], vec![(0, 2..3)]]
+        );
+    }
+
+    #[test]
+    pub fn test_paginate_orphans() {
+        let options = FlowOptions::default();
+        // only one line of the second paragraph fits below the first, so it starts on the next page
+        assert_eq!(
+            layout(&lines(&[2, 4]), 30.0, &options),
+            vec![vec![(0, 0..2)], vec![(1, 0..2)], vec![(1, 2..4)]]
+        );
+        let placements = paginate(&lines(&[2, 4]), 30.0, &options);
+        assert_eq!(placements[1][0].y, 0.0);
+        // spacing is added between paragraphs on the same page
+        let spaced = FlowOptions {
+            paragraph_spacing: 5.0,
+            ..options
+        };
+        let placements = paginate(&lines(&[1, 1]), 100.0, &spaced);
+        assert_eq!(placements[0][1].y, 15.0);
+    }
+
+    #[test]
+    pub fn test_flow_non_ascii_text() {
+        let fontmgr = FontMgr::create_custom_data(&mut [SkiaData::new_with_copy(include_bytes!(
+            "../examples/helper/fira_code_regular.ttf"
+        ))])
+        .expect("failed to create font manager");
+        let mut fc = FontCollection::default();
+        fc.set_default_font_manager(&fontmgr);
+        let mut style = ParagraphStyle::default();
+        let mut text_style = style.get_text_style();
+        text_style.set_font_size(20.0);
+        style.set_text_style(&text_style);
+        // multi byte characters make utf-8 offsets run ahead of utf-16 offsets
+        let text = "Ça déménage, 世界 🎉 naïve café. ".repeat(30);
+        let mut builder = ParagraphBuider::new(&style, &fc);
+        builder.add_text(&text);
+        let mut paragraphs = vec![FlowParagraph::new(&mut builder)];
+        assert_eq!(paragraphs[0].text, text);
+
+        let content = Rect::new(0.0, 0.0, 200.0, 150.0);
+        let flow = TextFlow::new(&mut paragraphs, &content, &FlowOptions::default());
+        let paragraph = &mut paragraphs[0];
+        let count = paragraph.paragraph.line_number();
+        assert!(flow.get_page_count() > 1, "text should not fit on one page");
+
+        // every line has a height of its own and follows the previous one
+        let extents = line_extents(&mut paragraph.paragraph, &paragraph.text);
+        assert_eq!(extents.len(), count);
+        let mut previous_bottom = 0.0;
+        for &(top, bottom) in &extents {
+            assert!(bottom - top > 1.0, "line has no height");
+            assert!(
+                top >= previous_bottom - EPSILON,
+                "line overlaps the previous line"
+            );
+            previous_bottom = bottom;
+        }
+        assert!(previous_bottom <= paragraph.paragraph.get_height() + EPSILON);
+
+        // every line is placed exactly once, in order, within the page
+        let mut next_line = 0;
+        for page in flow.get_pages() {
+            for slice in &page.slices {
+                assert_eq!(slice.lines.start, next_line);
+                assert!(slice.y + slice.source.end - slice.source.start <= 150.0 + EPSILON);
+                next_line = slice.lines.end;
+            }
+        }
+        assert_eq!(next_line, count);
+    }
+}
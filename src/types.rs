@@ -504,6 +504,13 @@ impl SamplingOptions {
         fFilter: FilterMode::LINEAR_SK_FILTER_MODE,
         fMipmap: MipmapMode::LINEAR_SK_MIPMAP_MODE,
     };
+    pub const NEAREST: Self = SamplingOptions {
+        fMaxAniso: 0,
+        fUseCubic: false,
+        fCubic: CubicResampler { fB: 0.0, fC: 0.0 }.into_native(),
+        fFilter: FilterMode::NEAREST_SK_FILTER_MODE,
+        fMipmap: MipmapMode::NONE_SK_MIPMAP_MODE,
+    };
 }
 impl Default for SamplingOptions {
     fn default() -> Self {
@@ -627,4 +634,16 @@ impl ImageInfo {
     pub fn bytes_per_pixel(&self) -> u8 {
         bytes_per_pixel(self.0.colorType)
     }
+    /// the smallest row bytes value which can hold a row of `width` pixels.
+    pub fn min_row_bytes(&self) -> usize {
+        self.0.width.max(0) as usize * self.bytes_per_pixel() as usize
+    }
+    /// the minimum length of a pixel buffer with `row_bytes` stride for this info.
+    /// The last row doesn't need any padding, so this is `(height - 1) * row_bytes + min_row_bytes`.
+    pub fn compute_byte_size(&self, row_bytes: usize) -> usize {
+        match self.0.height.max(0) as usize {
+            0 => 0,
+            height => (height - 1) * row_bytes + self.min_row_bytes(),
+        }
+    }
 }
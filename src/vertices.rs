@@ -0,0 +1,287 @@
+use std::fmt::Display;
+
+use crate::bindings::*;
+use crate::{color::Color, Point, VerticesVertexMode};
+
+crate::skia_wrapper!(
+    nvrefcnt,
+    Vertices,
+    sk_vertices_t,
+    sk_vertices_unref,
+    sk_vertices_ref
+);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerticesError {
+    /// texture coordinates or colors don't have one entry per position
+    LengthMismatch { positions: usize, actual: usize },
+    /// an index is not smaller than the number of positions
+    IndexOutOfRange { index: u16, vertex_count: usize },
+    /// indices were set to an empty list. Use `None` to draw the vertices in order.
+    EmptyIndices,
+    /// too many vertices or indices for skia
+    TooLarge,
+}
+impl Display for VerticesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VerticesError::LengthMismatch { positions, actual } => {
+                write!(
+                    f,
+                    "expected {positions} entries (one per position), got {actual}"
+                )
+            }
+            VerticesError::IndexOutOfRange {
+                index,
+                vertex_count,
+            } => write!(
+                f,
+                "index {index} is out of range for {vertex_count} vertices"
+            ),
+            VerticesError::EmptyIndices => write!(f, "indices are set but empty"),
+            VerticesError::TooLarge => write!(f, "too many vertices or indices"),
+        }
+    }
+}
+impl std::error::Error for VerticesError {}
+
+/// Collects the data of a triangle mesh and copies it into an immutable [Vertices].
+///
+/// The builder can be kept around to change the positions and build new [Vertices] every frame.
+///
+/// ```rust,ignore
+/// let mut builder = VerticesBuilder::new(VerticesVertexMode::TRIANGLE_FAN_SK_VERTICES_VERTEX_MODE, points);
+/// builder.set_colors(Some(colors));
+/// canvas.draw_vertices(&builder.build()?, BlendMode::MODULATE_SK_BLENDMODE, &paint);
+/// ```
+#[derive(Debug, Clone)]
+pub struct VerticesBuilder {
+    mode: VerticesVertexMode,
+    positions: Vec<Point>,
+    tex_coords: Option<Vec<Point>>,
+    colors: Option<Vec<Color>>,
+    indices: Option<Vec<u16>>,
+}
+impl VerticesBuilder {
+    /// `mode` decides how the vertices (or indices) form triangles:
+    /// every 3 of them, a strip (each one after the first two) or a fan around the first one.
+    pub fn new(mode: VerticesVertexMode, positions: impl Into<Vec<Point>>) -> Self {
+        Self {
+            mode,
+            positions: positions.into(),
+            tex_coords: None,
+            colors: None,
+            indices: None,
+        }
+    }
+    /// coordinates into the paint's shader. Without them, the positions are used.
+    pub fn set_tex_coords(&mut self, tex_coords: Option<Vec<Point>>) -> &mut Self {
+        self.tex_coords = tex_coords;
+        self
+    }
+    /// colors are blended with the paint's shader (or color) using the blend mode of [crate::canvas::Canvas::draw_vertices].
+    pub fn set_colors(&mut self, colors: Option<Vec<Color>>) -> &mut Self {
+        self.colors = colors;
+        self
+    }
+    /// if set, the triangles are formed from the indices instead of the vertices in order.
+    /// An empty list is an error when building, use `None` instead.
+    pub fn set_indices(&mut self, indices: Option<Vec<u16>>) -> &mut Self {
+        self.indices = indices;
+        self
+    }
+    pub fn get_mode(&self) -> VerticesVertexMode {
+        self.mode
+    }
+    pub fn positions(&self) -> &[Point] {
+        &self.positions
+    }
+    /// eg: to deform the mesh before building it again
+    pub fn positions_mut(&mut self) -> &mut Vec<Point> {
+        &mut self.positions
+    }
+    pub fn tex_coords_mut(&mut self) -> Option<&mut Vec<Point>> {
+        self.tex_coords.as_mut()
+    }
+    pub fn colors_mut(&mut self) -> Option<&mut Vec<Color>> {
+        self.colors.as_mut()
+    }
+    pub fn indices_mut(&mut self) -> Option<&mut Vec<u16>> {
+        self.indices.as_mut()
+    }
+    /// number of triangles drawn from the vertices (or indices). Leftover vertices that don't form a triangle are ignored.
+    pub fn get_triangle_count(&self) -> usize {
+        let count = self.indices.as_ref().map_or(self.positions.len(), Vec::len);
+        match self.mode {
+            VerticesVertexMode::TRIANGLES_SK_VERTICES_VERTEX_MODE => count / 3,
+            _ => count.saturating_sub(2),
+        }
+    }
+    pub fn validate(&self) -> Result<(), VerticesError> {
+        let count = self.positions.len();
+        if count > i32::MAX as usize
+            || self
+                .indices
+                .as_ref()
+                .is_some_and(|i| i.len() > i32::MAX as usize)
+        {
+            return Err(VerticesError::TooLarge);
+        }
+        let lengths = [
+            self.tex_coords.as_ref().map(Vec::len),
+            self.colors.as_ref().map(Vec::len),
+        ];
+        if let Some(actual) = lengths.into_iter().flatten().find(|&l| l != count) {
+            return Err(VerticesError::LengthMismatch {
+                positions: count,
+                actual,
+            });
+        }
+        if self.indices.as_ref().is_some_and(Vec::is_empty) {
+            return Err(VerticesError::EmptyIndices);
+        }
+        if let Some(&index) = self
+            .indices
+            .iter()
+            .flatten()
+            .find(|&&i| i as usize >= count)
+        {
+            return Err(VerticesError::IndexOutOfRange {
+                index,
+                vertex_count: count,
+            });
+        }
+        Ok(())
+    }
+    /// validates the data and copies it into [Vertices].
+    /// Fails with [VerticesError::TooLarge] if skia can't allocate them.
+    pub fn build(&self) -> Result<Vertices, VerticesError> {
+        self.validate()?;
+        unsafe {
+            Vertices::try_from_owned_ptr(sk_vertices_make_copy(
+                self.mode,
+                self.positions.len() as _,
+                self.positions.as_ptr(),
+                self.tex_coords
+                    .as_ref()
+                    .map_or(std::ptr::null(), |t| t.as_ptr()),
+                self.colors
+                    .as_ref()
+                    .map_or(std::ptr::null(), |c| c.as_ptr() as _),
+                self.indices.as_ref().map_or(0, Vec::len) as _,
+                self.indices
+                    .as_ref()
+                    .map_or(std::ptr::null(), |i| i.as_ptr()),
+            ))
+            .ok_or(VerticesError::TooLarge)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{VerticesBuilder, VerticesError};
+    use crate::{Color, Point, VerticesVertexMode};
+
+    fn square() -> Vec<Point> {
+        vec![
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 0.0),
+            Point::new(1.0, 1.0),
+            Point::new(0.0, 1.0),
+        ]
+    }
+
+    #[test]
+    pub fn test_vertices_index_out_of_range() {
+        let mut builder = VerticesBuilder::new(
+            VerticesVertexMode::TRIANGLES_SK_VERTICES_VERTEX_MODE,
+            square(),
+        );
+        builder.set_indices(Some(vec![0, 1, 2, 0, 2, 3]));
+        assert_eq!(builder.validate(), Ok(()));
+        builder.set_indices(Some(vec![0, 1, 2, 0, 2, 4]));
+        assert_eq!(
+            builder.validate(),
+            Err(VerticesError::IndexOutOfRange {
+                index: 4,
+                vertex_count: 4
+            })
+        );
+        builder.set_indices(Some(vec![]));
+        assert_eq!(builder.validate(), Err(VerticesError::EmptyIndices));
+        builder.set_indices(None);
+        assert_eq!(builder.validate(), Ok(()));
+    }
+
+    #[test]
+    pub fn test_vertices_attribute_lengths() {
+        let mut builder = VerticesBuilder::new(
+            VerticesVertexMode::TRIANGLE_FAN_SK_VERTICES_VERTEX_MODE,
+            square(),
+        );
+        builder
+            .set_tex_coords(Some(square()))
+            .set_colors(Some(vec![Color::RED; 4]));
+        assert_eq!(builder.validate(), Ok(()));
+        builder.set_colors(Some(vec![Color::RED; 3]));
+        assert_eq!(
+            builder.validate(),
+            Err(VerticesError::LengthMismatch {
+                positions: 4,
+                actual: 3
+            })
+        );
+        builder.set_colors(None);
+        builder.set_tex_coords(Some(vec![Point::new(0.0, 0.0); 5]));
+        assert_eq!(
+            builder.validate(),
+            Err(VerticesError::LengthMismatch {
+                positions: 4,
+                actual: 5
+            })
+        );
+        // a position added without a matching tex coord
+        builder.set_tex_coords(Some(square()));
+        builder.positions_mut().push(Point::new(0.5, 2.0));
+        assert_eq!(
+            builder.validate(),
+            Err(VerticesError::LengthMismatch {
+                positions: 5,
+                actual: 4
+            })
+        );
+    }
+
+    #[test]
+    pub fn test_vertices_triangle_count() {
+        let mut builder = VerticesBuilder::new(
+            VerticesVertexMode::TRIANGLES_SK_VERTICES_VERTEX_MODE,
+            square(),
+        );
+        // the 4th vertex is left over
+        assert_eq!(builder.get_triangle_count(), 1);
+        builder.set_indices(Some(vec![0, 1, 2, 0, 2, 3]));
+        assert_eq!(builder.get_triangle_count(), 2);
+
+        let mut builder = VerticesBuilder::new(
+            VerticesVertexMode::TRIANGLE_STRIP_SK_VERTICES_VERTEX_MODE,
+            square(),
+        );
+        assert_eq!(builder.get_triangle_count(), 2);
+        builder.set_indices(Some(vec![0, 1]));
+        assert_eq!(builder.get_triangle_count(), 0);
+
+        let builder = VerticesBuilder::new(
+            VerticesVertexMode::TRIANGLE_FAN_SK_VERTICES_VERTEX_MODE,
+            square(),
+        );
+        assert_eq!(builder.get_triangle_count(), 2);
+        let builder = VerticesBuilder::new(
+            VerticesVertexMode::TRIANGLE_FAN_SK_VERTICES_VERTEX_MODE,
+            vec![],
+        );
+        assert_eq!(builder.get_triangle_count(), 0);
+        assert_eq!(builder.validate(), Ok(()));
+    }
+}